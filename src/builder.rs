@@ -0,0 +1,110 @@
+use crate::{
+    google::{LogSeverity, MonitoredResource, ServiceContext},
+    layer::Layer,
+};
+use std::io;
+use tracing_core::{Level, Subscriber};
+use tracing_subscriber::{fmt::MakeWriter, registry::LookupSpan};
+
+/// Builder for a Stackdriver [`Layer`], created with [`Stackdriver::builder`](Layer::builder).
+/// Its methods set the most common options of the Layer, while
+/// [`configure`](Self::configure) reaches every other option, and [`build`](Self::build)
+/// returns the configured Layer.
+pub struct StackdriverBuilder<S, W = fn() -> io::Stdout>(Layer<S, W>)
+where
+    S: Subscriber + for<'span> LookupSpan<'span>;
+
+impl<S, W> StackdriverBuilder<S, W>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    W: for<'writer> MakeWriter<'writer> + 'static,
+{
+    pub(crate) fn new(layer: Layer<S, W>) -> Self {
+        Self(layer)
+    }
+
+    /// Sets the MakeWriter that the Layer being built will use to write events (see
+    /// [`Layer::with_writer`])
+    pub fn with_writer<M>(self, make_writer: M) -> StackdriverBuilder<S, M>
+    where
+        M: for<'writer> MakeWriter<'writer> + 'static,
+    {
+        StackdriverBuilder(self.0.with_writer(make_writer))
+    }
+
+    /// Configures a handler for the errors of the Layer's writers and the warnings of its event
+    /// formatter (see [`Layer::on_error`])
+    pub fn on_error<F>(self, handler: F) -> Self
+    where
+        F: Fn(io::Error) + Send + Sync + 'static,
+    {
+        Self(self.0.on_error(handler))
+    }
+
+    /// Configures the Google Cloud project ID used to qualify `trace_id` fields (see
+    /// [`Layer::with_project_id`])
+    pub fn with_project_id(self, project_id: impl Into<String>) -> Self {
+        Self(self.0.with_project_id(project_id))
+    }
+
+    /// Configures static labels that are included in every Event (see [`Layer::with_labels`])
+    pub fn with_labels<K, V>(self, labels: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: ToString,
+    {
+        Self(self.0.with_labels(labels))
+    }
+
+    /// Configures whether or not Events will include source locations (see
+    /// [`Layer::with_source_location`])
+    pub fn with_source_location(self, include_source_location: bool) -> Self {
+        Self(self.0.with_source_location(include_source_location))
+    }
+
+    /// Configures the mapping of `tracing` Levels to severities (see
+    /// [`Layer::with_severity_mapping`])
+    pub fn with_severity_mapping<F>(self, severity_mapping: F) -> Self
+    where
+        F: Fn(&Level) -> LogSeverity + Send + Sync + 'static,
+    {
+        Self(self.0.with_severity_mapping(severity_mapping))
+    }
+
+    /// Configures the MonitoredResource of every Event (see [`Layer::with_resource`])
+    pub fn with_resource(self, resource: MonitoredResource) -> Self {
+        Self(self.0.with_resource(resource))
+    }
+
+    /// Reports error Events to Error Reporting with a service context (see
+    /// [`Layer::with_error_reporting`])
+    pub fn with_error_reporting(self, service_context: ServiceContext) -> Self {
+        Self(self.0.with_error_reporting(service_context))
+    }
+
+    /// Configures whether or not Events will include a generated insert ID (see
+    /// [`Layer::with_auto_insert_id`])
+    pub fn with_auto_insert_id(self, auto_insert_id: bool) -> Self {
+        Self(self.0.with_auto_insert_id(auto_insert_id))
+    }
+
+    /// Configures any other option of the Layer being built with its own `with_*` methods
+    pub fn configure(self, configure: impl FnOnce(Layer<S, W>) -> Layer<S, W>) -> Self {
+        Self(configure(self.0))
+    }
+
+    /// Returns the configured Layer
+    pub fn build(self) -> Layer<S, W> {
+        self.0
+    }
+}
+
+impl<S, W> From<StackdriverBuilder<S, W>> for Layer<S, W>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    W: for<'writer> MakeWriter<'writer> + 'static,
+{
+    fn from(builder: StackdriverBuilder<S, W>) -> Self {
+        builder.build()
+    }
+}
@@ -0,0 +1,27 @@
+use time::OffsetDateTime;
+
+/// Source of the timestamps of Events, e.g. for deterministic timestamps in tests
+pub trait Clock: Send + Sync {
+    /// The current time
+    fn now(&self) -> OffsetDateTime;
+}
+
+/// The default system clock
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc()
+    }
+}
+
+/// Clocks can also be closures, e.g. `|| OffsetDateTime::UNIX_EPOCH`
+impl<F> Clock for F
+where
+    F: Fn() -> OffsetDateTime + Send + Sync,
+{
+    fn now(&self) -> OffsetDateTime {
+        self()
+    }
+}
@@ -0,0 +1,67 @@
+use std::time::Duration;
+use valuable::{Fields, NamedField, NamedValues, StructDef, Structable, Valuable, Value, Visit};
+
+static DURATION_FIELDS: &[NamedField<'static>] =
+    &[NamedField::new("secs"), NamedField::new("nanos")];
+
+/// `valuable`-compatible wrapper for recording a `std::time::Duration` as a field, which is
+/// serialized in the `DurationFormat` of the layer (numeric milliseconds by default)
+#[cfg_attr(docsrs, doc(cfg(feature = "valuable")))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DurationValue(pub Duration);
+
+impl From<Duration> for DurationValue {
+    fn from(duration: Duration) -> Self {
+        Self(duration)
+    }
+}
+
+impl Valuable for DurationValue {
+    fn as_value(&self) -> Value<'_> {
+        Value::Structable(self)
+    }
+
+    fn visit(&self, visit: &mut dyn Visit) {
+        let values = [
+            Value::U64(self.0.as_secs()),
+            Value::U32(self.0.subsec_nanos()),
+        ];
+
+        visit.visit_named_fields(&NamedValues::new(DURATION_FIELDS, &values));
+    }
+}
+
+impl Structable for DurationValue {
+    fn definition(&self) -> StructDef<'_> {
+        StructDef::new_static("Duration", Fields::Named(DURATION_FIELDS))
+    }
+}
+
+/// Duration of a `Duration` structure (with `secs` and `nanos` fields), if a value is one
+pub(crate) fn as_duration(value: &Value<'_>) -> Option<Duration> {
+    /// Visitor that collects the fields of a `Duration` structure
+    #[derive(Default)]
+    struct DurationVisitor(Option<Duration>);
+
+    impl Visit for DurationVisitor {
+        fn visit_value(&mut self, _: Value<'_>) {}
+
+        fn visit_named_fields(&mut self, named_values: &NamedValues<'_>) {
+            let secs = named_values.get_by_name("secs").and_then(Value::as_u64);
+            let nanos = named_values.get_by_name("nanos").and_then(Value::as_u32);
+
+            if let (Some(secs), Some(nanos)) = (secs, nanos) {
+                self.0 = Some(Duration::new(secs, nanos));
+            }
+        }
+    }
+
+    match value {
+        Value::Structable(structable) if structable.definition().name() == "Duration" => {
+            let mut visitor = DurationVisitor::default();
+            structable.visit(&mut visitor);
+            visitor.0
+        }
+        _ => None,
+    }
+}
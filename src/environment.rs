@@ -0,0 +1,197 @@
+#[cfg(any(docsrs, feature = "resource-detection"))]
+use crate::google::MonitoredResource;
+#[cfg(any(docsrs, feature = "resource-detection"))]
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+/// Environment variables that commonly carry the Google Cloud project ID, in order of precedence
+const PROJECT_ID_VARIABLES: &[&str] = &["GOOGLE_CLOUD_PROJECT", "GCP_PROJECT"];
+
+/// Detect the Google Cloud project ID of the current environment, first from well-known
+/// environment variables and then (with the `metadata` feature) from the metadata server
+pub(crate) fn detect_project_id() -> Option<String> {
+    let project_id = project_id_variable();
+
+    #[cfg(feature = "metadata")]
+    let project_id = project_id.or_else(|| metadata::get("project/project-id"));
+
+    project_id
+}
+
+/// Read the Google Cloud project ID from the first well-known environment variable that's set
+fn project_id_variable() -> Option<String> {
+    PROJECT_ID_VARIABLES
+        .iter()
+        .filter_map(|variable| std::env::var(variable).ok())
+        .find(|project_id| !project_id.is_empty())
+}
+
+/// Monitored resource of the current environment, detected on a best-effort basis by
+/// [`detect_resource`]
+#[cfg_attr(docsrs, doc(cfg(feature = "resource-detection")))]
+#[cfg(any(docsrs, feature = "resource-detection"))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DetectedResource {
+    /// [Monitored resource type](https://cloud.google.com/logging/docs/api/v2/resource-list) of
+    /// the environment (e.g. `cloud_run_revision`, `k8s_container`, or `gce_instance`), if any
+    pub resource_type: Option<String>,
+    /// Labels describing the resource (e.g. `service_name`, `revision_name`, and `location`),
+    /// suitable for use as static labels with `Layer::with_labels`
+    pub labels: BTreeMap<String, String>,
+}
+
+#[cfg(any(docsrs, feature = "resource-detection"))]
+impl DetectedResource {
+    /// Convert a detected resource with a known type into a [`MonitoredResource`] that can be
+    /// included in every Event with `Layer::with_resource`
+    pub fn into_monitored_resource(self) -> Option<MonitoredResource> {
+        let labels = self.labels;
+
+        self.resource_type.map(|resource_type| MonitoredResource {
+            resource_type,
+            labels,
+        })
+    }
+}
+
+/// Detect the monitored resource of the current environment on Cloud Run, GKE, or GCE from
+/// well-known environment variables and (with the `metadata` feature) the metadata server. Since
+/// detection is best-effort, unknown environments (e.g. local development) produce a resource
+/// without a type or labels, and metadata server requests are skipped entirely when the metadata
+/// server is unreachable.
+#[cfg_attr(docsrs, doc(cfg(feature = "resource-detection")))]
+#[cfg(any(docsrs, feature = "resource-detection"))]
+pub fn detect_resource() -> DetectedResource {
+    let variable = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+    // the metadata server's project ID doubles as the only probe of whether it's reachable
+    #[cfg(feature = "metadata")]
+    let metadata_project_id = metadata::get("project/project-id");
+
+    #[cfg(feature = "metadata")]
+    let metadata = {
+        let available = metadata_project_id.is_some();
+        move |path: &str| available.then(|| metadata::get(path)).flatten()
+    };
+
+    #[cfg(not(feature = "metadata"))]
+    let (metadata_project_id, metadata) = (None::<String>, |_: &str| None::<String>);
+
+    // metadata paths like "projects/123/zones/us-central1-a" end with the relevant name
+    let last_segment = |value: String| value.rsplit('/').next().map(ToOwned::to_owned);
+
+    let mut labels = BTreeMap::new();
+
+    if let Some(project_id) = project_id_variable().or(metadata_project_id) {
+        labels.insert("project_id".to_owned(), project_id);
+    }
+
+    let resource_type = if let Some(service_name) = variable("K_SERVICE") {
+        labels.insert("service_name".to_owned(), service_name);
+        labels.extend(variable("K_REVISION").map(|name| ("revision_name".to_owned(), name)));
+        labels.extend(
+            variable("K_CONFIGURATION").map(|name| ("configuration_name".to_owned(), name)),
+        );
+        labels.extend(
+            metadata("instance/region")
+                .and_then(last_segment)
+                .map(|location| ("location".to_owned(), location)),
+        );
+
+        Some("cloud_run_revision")
+    } else if variable("KUBERNETES_SERVICE_HOST").is_some() {
+        labels.extend(variable("HOSTNAME").map(|name| ("pod_name".to_owned(), name)));
+        labels.extend(variable("POD_NAMESPACE").map(|name| ("namespace_name".to_owned(), name)));
+        labels.extend(variable("CONTAINER_NAME").map(|name| ("container_name".to_owned(), name)));
+        labels.extend(
+            metadata("instance/attributes/cluster-name")
+                .map(|name| ("cluster_name".to_owned(), name)),
+        );
+        labels.extend(
+            metadata("instance/attributes/cluster-location")
+                .map(|location| ("location".to_owned(), location)),
+        );
+
+        Some("k8s_container")
+    } else if let Some(instance_id) = metadata("instance/id") {
+        labels.insert("instance_id".to_owned(), instance_id);
+        labels.extend(
+            metadata("instance/zone")
+                .and_then(last_segment)
+                .map(|zone| ("zone".to_owned(), zone)),
+        );
+
+        Some("gce_instance")
+    } else {
+        None
+    };
+
+    DetectedResource {
+        resource_type: resource_type.map(ToOwned::to_owned),
+        labels,
+    }
+}
+
+/// Name of the current host, resolved (from the `HOSTNAME` environment variable or the kernel's
+/// hostname files on Unix-like systems) on first use and cached for the life of the process
+pub(crate) fn hostname() -> Option<&'static str> {
+    static HOSTNAME: OnceLock<Option<String>> = OnceLock::new();
+
+    HOSTNAME
+        .get_or_init(|| {
+            std::env::var("HOSTNAME")
+                .ok()
+                .or_else(|| std::fs::read_to_string("/proc/sys/kernel/hostname").ok())
+                .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+                .map(|hostname| hostname.trim().to_owned())
+                .filter(|hostname| !hostname.is_empty())
+        })
+        .as_deref()
+}
+
+/// Minimal, blocking client for the [metadata
+/// server](https://cloud.google.com/compute/docs/metadata/overview) available on GCE, GKE, Cloud
+/// Run, and other Google Cloud runtimes
+#[cfg(feature = "metadata")]
+pub(crate) mod metadata {
+    use std::{
+        io::{Read, Write},
+        net::{SocketAddr, TcpStream},
+        time::Duration,
+    };
+
+    /// Link-local address of the metadata server, which avoids a DNS lookup of
+    /// `metadata.google.internal` (which can be slow to fail outside of Google Cloud)
+    const ADDRESS: ([u8; 4], u16) = ([169, 254, 169, 254], 80);
+
+    /// Upper bound on each network operation, so that local development isn't blocked for long
+    const TIMEOUT: Duration = Duration::from_millis(500);
+
+    /// Fetch a metadata value by path (e.g. `project/project-id`), returning `None` on any error
+    pub(crate) fn get(path: &str) -> Option<String> {
+        let mut stream = TcpStream::connect_timeout(&SocketAddr::from(ADDRESS), TIMEOUT).ok()?;
+        stream.set_read_timeout(Some(TIMEOUT)).ok()?;
+        stream.set_write_timeout(Some(TIMEOUT)).ok()?;
+
+        write!(
+            stream,
+            "GET /computeMetadata/v1/{path} HTTP/1.0\r\n\
+             Host: metadata.google.internal\r\n\
+             Metadata-Flavor: Google\r\n\r\n"
+        )
+        .ok()?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).ok()?;
+
+        let (head, body) = response.split_once("\r\n\r\n")?;
+        let status = head.split_whitespace().nth(1)?;
+        let body = body.trim();
+
+        if status == "200" && !body.is_empty() {
+            Some(body.to_owned())
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::fmt;
+
+/// Snapshot of an error report (e.g. an `anyhow::Error`) for recording as a
+/// `std::error::Error` field, which is serialized like any other error along with the report's
+/// backtrace in the Event's `stack_trace` field (when one was captured)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErrorValue {
+    pub(crate) message: String,
+    pub(crate) causes: Vec<String>,
+    pub(crate) backtrace: Option<String>,
+}
+
+impl fmt::Display for ErrorValue {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ErrorValue {}
+
+/// Snapshot an `anyhow::Error` (including its chain of causes and backtrace) for recording as a
+/// field, e.g. `tracing::error!(error = &error_value(&error) as &dyn std::error::Error)`
+#[cfg_attr(docsrs, doc(cfg(feature = "anyhow")))]
+#[cfg(any(docsrs, feature = "anyhow"))]
+pub fn error_value(error: &anyhow::Error) -> ErrorValue {
+    let backtrace = error.backtrace();
+
+    ErrorValue {
+        message: error.to_string(),
+        causes: error.chain().skip(1).map(ToString::to_string).collect(),
+        backtrace: match backtrace.status() {
+            std::backtrace::BacktraceStatus::Captured => Some(backtrace.to_string()),
+            _ => None,
+        },
+    }
+}
+
+/// Snapshot an `eyre::Report` (including its chain of causes) for recording as a field, e.g.
+/// `tracing::error!(error = &report_value(&report) as &dyn std::error::Error)`. Since `eyre`
+/// handlers don't expose their backtraces, none are included.
+#[cfg_attr(docsrs, doc(cfg(feature = "eyre")))]
+#[cfg(any(docsrs, feature = "eyre"))]
+pub fn report_value(report: &eyre::Report) -> ErrorValue {
+    ErrorValue {
+        message: report.to_string(),
+        causes: report.chain().skip(1).map(ToString::to_string).collect(),
+        backtrace: None,
+    }
+}
+
+#[cfg(all(tracing_unstable, feature = "valuable"))]
+static ERROR_VALUE_FIELDS: &[valuable::NamedField<'static>] = &[
+    valuable::NamedField::new("message"),
+    valuable::NamedField::new("causes"),
+    valuable::NamedField::new("stackTrace"),
+];
+
+#[cfg_attr(docsrs, doc(cfg(feature = "valuable")))]
+#[cfg(any(docsrs, all(tracing_unstable, feature = "valuable")))]
+impl valuable::Valuable for ErrorValue {
+    fn as_value(&self) -> valuable::Value<'_> {
+        valuable::Value::Structable(self)
+    }
+
+    fn visit(&self, visit: &mut dyn valuable::Visit) {
+        // messages include their causes, like those of `std::error::Error` fields
+        let message = std::iter::once(&self.message)
+            .chain(&self.causes)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(": ");
+        let fields = match self.backtrace {
+            Some(_) => ERROR_VALUE_FIELDS,
+            None => &ERROR_VALUE_FIELDS[..2],
+        };
+        let backtrace = self.backtrace.as_deref().unwrap_or_default();
+        let values = [
+            valuable::Valuable::as_value(&message),
+            valuable::Valuable::as_value(&self.causes),
+            valuable::Valuable::as_value(&backtrace),
+        ];
+
+        visit.visit_named_fields(&valuable::NamedValues::new(fields, &values[..fields.len()]));
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "valuable")))]
+#[cfg(any(docsrs, all(tracing_unstable, feature = "valuable")))]
+impl valuable::Structable for ErrorValue {
+    fn definition(&self) -> valuable::StructDef<'_> {
+        valuable::StructDef::new_dynamic("ErrorValue", valuable::Fields::Named(&[]))
+    }
+}
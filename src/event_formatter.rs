@@ -1,13 +1,24 @@
 use crate::{
-    google::LogSeverity,
-    serializers::{SerializableContext, SerializableSpan, SourceLocation},
+    clock::{Clock, SystemClock},
+    field_filter::FieldFilter,
+    google::{
+        ControlCharHandling, ErrorFormat, LogSeverity, MonitoredResource, SeverityFormat,
+        TimestampStyle, TraceSeverity,
+    },
+    serializers::{
+        span_fields, Host, Process, SerializableContext, SerializableSpan, SourceLocation,
+        SpanFields, Thread, Timestamp,
+    },
+    trace_context::TraceContext,
     visitor::Visitor,
-    writer::WriteAdaptor,
+    writer::{ErrorHandler, WriteAdaptor},
 };
-use serde::ser::{SerializeMap, Serializer as _};
-use std::fmt;
-use time::{format_description::well_known::Rfc3339, OffsetDateTime};
-use tracing_core::{Event, Subscriber};
+use serde::ser::Serializer as _;
+use serde_json::value::RawValue;
+use std::{collections::BTreeMap, fmt, io};
+use time::{OffsetDateTime, UtcOffset};
+use tracing_core::{Event, Level, Metadata, Subscriber};
+use tracing_log::NormalizeEvent;
 use tracing_subscriber::{
     field::VisitOutput,
     fmt::{
@@ -35,27 +46,132 @@ impl From<Error> for fmt::Error {
     }
 }
 
+/// Span fields that are mapped to special Cloud Trace LogEntry fields
+const TRACE_FIELDS: &[&str] = &["trace_id", "span_id", "trace_sampled"];
+
+/// Span field prefixes that are mapped to special LogEntry fields
+const INHERITED_PREFIXES: &[&str] = &["labels.", "operation."];
+
+/// Whether or not a span field is inherited by the special LogEntry fields of its events
+fn is_inherited(key: &str) -> bool {
+    TRACE_FIELDS.contains(&key)
+        || INHERITED_PREFIXES
+            .iter()
+            .any(|prefix| key.starts_with(prefix))
+}
+
+/// Format a timestamp as an RFC3339 UTC timestamp with a fixed nanosecond precision (e.g.
+/// `2023-05-01T12:00:00.123456789Z`), so that entries logged within the same millisecond (or
+/// on a whole second) keep their order when sorted by time
+fn format_rfc3339_nanos(time: OffsetDateTime) -> String {
+    let time = time.to_offset(UtcOffset::UTC);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:09}Z",
+        time.year(),
+        u8::from(time.month()),
+        time.day(),
+        time.hour(),
+        time.minute(),
+        time.second(),
+        time.nanosecond()
+    )
+}
+
+/// Custom mapping from tracing Levels to LogSeverity levels
+pub(crate) type SeverityMapping = Box<dyn Fn(&Level) -> LogSeverity + Send + Sync>;
+
+/// Generator of insert IDs for Events without an explicit `insert_id` field
+pub(crate) type InsertIdGenerator = Box<dyn Fn(&Event<'_>) -> String + Send + Sync>;
+
+/// Custom textual representation of `TimestampStyle::Rfc3339` Event timestamps
+pub(crate) type TimeFormatter = Box<dyn Fn(OffsetDateTime, &mut String) + Send + Sync>;
+
+/// Custom decision of which Events (and their LogSeverity) are marked as error events
+pub(crate) type ErrorReportingFilter = Box<dyn Fn(&Event<'_>, LogSeverity) -> bool + Send + Sync>;
+
+/// Mapping from Event metadata to a custom log name
+pub(crate) type LogNameMapper = Box<dyn Fn(&Metadata<'_>) -> Option<String> + Send + Sync>;
+
 /// Tracing Event formatter for Stackdriver layers
 pub struct EventFormatter {
     pub(crate) include_source_location: bool,
-    #[cfg(feature = "opentelemetry")]
-    pub(crate) cloud_trace_configuration: Option<crate::CloudTraceConfiguration>,
+    pub(crate) include_target: bool,
+    pub(crate) target_key: String,
+    pub(crate) include_thread_names: bool,
+    pub(crate) include_thread_ids: bool,
+    pub(crate) include_pid: bool,
+    pub(crate) include_hostname: bool,
+    pub(crate) include_span_list: bool,
+    pub(crate) flatten_spans: bool,
+    pub(crate) flattened_span_name_field: String,
+    pub(crate) span_name_key: String,
+    pub(crate) span_id_fallback: bool,
+    pub(crate) nest_dotted_fields: bool,
+    pub(crate) field_filter: FieldFilter,
+    pub(crate) max_field_bytes: Option<usize>,
+    pub(crate) control_char_handling: ControlCharHandling,
+    pub(crate) project_id: Option<String>,
+    pub(crate) labels: BTreeMap<String, String>,
+    pub(crate) resource: Option<MonitoredResource>,
+    pub(crate) insert_id_generator: Option<InsertIdGenerator>,
+    pub(crate) severity_mapping: Option<SeverityMapping>,
+    pub(crate) trace_severity: TraceSeverity,
+    pub(crate) severity_format: SeverityFormat,
+    pub(crate) log_name_mapper: Option<LogNameMapper>,
+    pub(crate) service_context: Option<Box<RawValue>>,
+    pub(crate) report_errors: bool,
+    pub(crate) stack_trace_threshold: Option<LogSeverity>,
+    pub(crate) error_reporting_threshold: LogSeverity,
+    pub(crate) error_reporting_filter: Option<ErrorReportingFilter>,
+    pub(crate) error_chain_depth: usize,
+    pub(crate) error_format: ErrorFormat,
+    #[cfg(all(tracing_unstable, feature = "valuable"))]
+    pub(crate) duration_format: crate::DurationFormat,
+    pub(crate) combine_error_messages: bool,
+    pub(crate) timestamp_style: TimestampStyle,
+    pub(crate) time_key: Option<String>,
+    pub(crate) time_formatter: Option<TimeFormatter>,
+    pub(crate) clock: Box<dyn Clock>,
+    pub(crate) pretty: bool,
+    pub(crate) message_field: String,
+    pub(crate) include_empty_messages: bool,
+    pub(crate) validate_latency: bool,
+    pub(crate) error_handler: Option<ErrorHandler>,
 }
 
 impl EventFormatter {
+    /// Pass a warning about an Event that was formatted anyway (e.g. with an invalid field value)
+    /// to the Layer's error handler, discarding it when no handler is configured
+    pub(crate) fn warn(&self, warning: fmt::Arguments<'_>) {
+        if let Some(error_handler) = &self.error_handler {
+            error_handler(io::Error::new(
+                io::ErrorKind::InvalidData,
+                warning.to_string(),
+            ));
+        }
+    }
+
     /// Internal event formatting for a given serializer
-    fn format_event<S>(
+    fn format_event<S, F>(
         &self,
         context: &FmtContext<S, JsonFields>,
-        mut serializer: serde_json::Serializer<WriteAdaptor>,
+        mut serializer: serde_json::Serializer<WriteAdaptor, F>,
         event: &Event,
     ) -> Result<(), Error>
     where
         S: Subscriber + for<'span> LookupSpan<'span>,
+        F: serde_json::ser::Formatter,
     {
-        let time = OffsetDateTime::now_utc().format(&Rfc3339)?;
-        let meta = event.metadata();
-        let severity = LogSeverity::from(meta.level());
+        let now = self.clock.now();
+        // forwarded `log` records carry their metadata in fields rather than in their callsites
+        let normalized_meta = event.normalized_metadata();
+        let meta = normalized_meta.as_ref().unwrap_or_else(|| event.metadata());
+        let severity = match &self.severity_mapping {
+            Some(severity_mapping) => severity_mapping(meta.level()),
+            None if *meta.level() == Level::TRACE => LogSeverity::from(self.trace_severity),
+            None => LogSeverity::from(meta.level()),
+        };
 
         let span = event
             .parent()
@@ -63,66 +179,216 @@ impl EventFormatter {
             .or_else(|| context.lookup_current());
 
         // FIXME: derive an accurate entry count ahead of time
-        let mut map = serializer.serialize_map(None)?;
+        let map = serializer.serialize_map(None)?;
+
+        // the visitor writes every field, keeping track of the keys taken by the layer's own
+        let mut visitor = Visitor::new(severity, event, meta, map, self);
 
         // serialize custom fields
-        map.serialize_entry("time", &time)?;
-        map.serialize_entry("target", &meta.target())?;
+        match self.timestamp_style {
+            TimestampStyle::Rfc3339 => {
+                let time = match &self.time_formatter {
+                    Some(time_formatter) => {
+                        let mut time = String::new();
+                        time_formatter(now, &mut time);
+                        time
+                    }
+                    None => format_rfc3339_nanos(now),
+                };
+
+                // formatted times are serialized (and escaped) like any other string
+                visitor.write(self.time_key.as_deref().unwrap_or("time"), &time)?
+            }
+            TimestampStyle::SecondsNanos => {
+                visitor.write("timestampSeconds", &now.unix_timestamp())?;
+                visitor.write("timestampNanos", &now.nanosecond())?;
+            }
+            TimestampStyle::Structured => visitor.write(
+                self.time_key.as_deref().unwrap_or("timestamp"),
+                &Timestamp {
+                    seconds: now.unix_timestamp(),
+                    nanos: now.nanosecond(),
+                },
+            )?,
+        }
+
+        if self.include_target {
+            visitor.write(&self.target_key, &meta.target())?;
+        }
+
+        if self.include_thread_names || self.include_thread_ids {
+            let thread = std::thread::current();
+            let name = thread.name().filter(|_| self.include_thread_names);
+            let id = self
+                .include_thread_ids
+                .then(|| format!("{:?}", thread.id()));
+
+            // unnamed threads are omitted entirely when only their names are included
+            if name.is_some() || id.is_some() {
+                visitor.write("thread", &Thread { name, id })?;
+            }
+        }
+
+        if self.include_pid {
+            let pid = std::process::id();
+            visitor.write("process", &Process { pid })?;
+        }
+
+        if let (true, Some(hostname)) = (self.include_hostname, crate::environment::hostname()) {
+            visitor.write("host", &Host { name: hostname })?;
+        }
 
         if self.include_source_location {
             if let Some(file) = meta.file() {
-                map.serialize_entry(
+                visitor.write(
                     "logging.googleapis.com/sourceLocation",
                     &SourceLocation {
                         file,
                         line: meta.line(),
+                        function: meta.module_path(),
                     },
                 )?;
             }
         }
 
+        if let Some(log_name) = self
+            .log_name_mapper
+            .as_ref()
+            .and_then(|log_name_mapper| log_name_mapper(meta))
+        {
+            visitor.write("logging.googleapis.com/logName", &log_name)?;
+        }
+
+        if let Some(resource) = &self.resource {
+            visitor.write("resource", resource)?;
+        }
+
         // serialize the current span and its leaves
-        if let Some(span) = span {
-            map.serialize_entry("span", &SerializableSpan::new(&span))?;
-            map.serialize_entry("spans", &SerializableContext::new(context))?;
+        if let Some(span) = &span {
+            if !self.flatten_spans {
+                visitor.write(
+                    "span",
+                    &SerializableSpan::new(span, &self.field_filter, &self.span_name_key),
+                )?;
+            }
+
+            if self.include_span_list {
+                visitor.write(
+                    "spans",
+                    &SerializableContext::new(span, &self.field_filter, &self.span_name_key),
+                )?;
+            }
+        }
+
+        // serialize the stackdriver-specific fields with a visitor
+        let inherited_fields = span.as_ref().map(span_fields).unwrap_or_default();
+
+        if let Some(insert_id_generator) = &self.insert_id_generator {
+            visitor.inherit("insert_id", insert_id_generator(event).into());
+        }
 
-            #[cfg(feature = "opentelemetry")]
-            if let (Some(crate::CloudTraceConfiguration { project_id }), Some(otel_data)) = (
-                self.cloud_trace_configuration.as_ref(),
-                span.extensions().get::<tracing_opentelemetry::OtelData>(),
-            ) {
-                use opentelemetry::trace::TraceContextExt;
+        // fall back to process-local span IDs, which are overridden by any recorded span IDs
+        if let (true, Some(span)) = (self.span_id_fallback, &span) {
+            visitor.inherit("span_id", span.id().into_u64().into());
+        }
+
+        // promote span fields destined for special LogEntry fields (or all of them when flattened)
+        for (key, value) in &inherited_fields {
+            if (self.flatten_spans && self.field_filter.allows(key)) || is_inherited(key) {
+                visitor.inherit(key, value.clone());
+            }
+        }
+
+        // promote the trace fields of the closest span (or ancestor) with each of them, where
+        // attached trace contexts take precedence over the recorded fields of the same span
+        let mut trace_fields = BTreeMap::new();
+
+        for span in span.iter().flat_map(|span| span.scope()) {
+            let extensions = span.extensions();
+
+            if let Some(TraceContext {
+                trace_id,
+                span_id,
+                sampled,
+            }) = extensions.get::<TraceContext>()
+            {
+                trace_fields
+                    .entry("trace_id")
+                    .or_insert_with(|| trace_id.as_str().into());
+
+                if let Some(span_id) = span_id {
+                    trace_fields
+                        .entry("span_id")
+                        .or_insert_with(|| (*span_id).into());
+                }
+
+                trace_fields
+                    .entry("trace_sampled")
+                    .or_insert_with(|| (*sampled).into());
+            }
+
+            if let Some(fields) = extensions.get::<SpanFields>() {
+                for key in TRACE_FIELDS {
+                    if let Some(value) = fields.get(key) {
+                        trace_fields.entry(*key).or_insert_with(|| value.clone());
+                    }
+                }
+            }
+
+            if trace_fields.len() == TRACE_FIELDS.len() {
+                break;
+            }
+        }
+
+        for (key, value) in trace_fields {
+            visitor.inherit(key, value);
+        }
+
+        if let (true, Some(span)) = (self.flatten_spans, &span) {
+            visitor.inherit(&self.flattened_span_name_field, span.name().into());
+        }
+
+        // OpenTelemetry span contexts take precedence over span fields, but not over event fields
+        #[cfg(feature = "opentelemetry")]
+        if let Some(span) = &span {
+            if let Some(otel_data) = span.extensions().get::<tracing_opentelemetry::OtelData>() {
+                use opentelemetry::trace::{SamplingDecision, TraceContextExt};
 
                 let builder = &otel_data.builder;
 
                 if let Some(span_id) = builder.span_id {
-                    map.serialize_entry("logging.googleapis.com/spanId", &span_id.to_string())?;
+                    visitor.inherit("span_id", span_id.to_string().into());
                 }
 
                 let (trace_id, trace_sampled) = if otel_data.parent_cx.has_active_span() {
                     let span_ref = otel_data.parent_cx.span();
                     let span_context = span_ref.span_context();
 
-                    (Some(span_context.trace_id()), span_context.is_sampled())
+                    (
+                        Some(span_context.trace_id()),
+                        Some(span_context.is_sampled()),
+                    )
                 } else {
-                    (builder.trace_id, false)
+                    // root spans are only sampled once their context is first needed (e.g. by
+                    // a child span), before which there's no sampling decision to include
+                    let trace_sampled = builder.sampling_result.as_ref().map(|sampling_result| {
+                        sampling_result.decision == SamplingDecision::RecordAndSample
+                    });
+
+                    (builder.trace_id, trace_sampled)
                 };
 
                 if let Some(trace_id) = trace_id {
-                    map.serialize_entry(
-                        "logging.googleapis.com/trace",
-                        &format!("projects/{project_id}/traces/{trace_id}",),
-                    )?;
+                    visitor.inherit("trace_id", trace_id.to_string().into());
                 }
 
-                if trace_sampled {
-                    map.serialize_entry("logging.googleapis.com/trace_sampled", &true)?;
+                // unsampled traces are explicitly marked as such, rather than omitted
+                if let Some(trace_sampled) = trace_sampled {
+                    visitor.inherit("trace_sampled", trace_sampled.into());
                 }
             }
         }
 
-        // serialize the stackdriver-specific fields with a visitor
-        let mut visitor = Visitor::new(severity, map);
         event.record(&mut visitor);
         visitor.finish().map_err(Error::from)?;
         Ok(())
@@ -142,8 +408,16 @@ where
     where
         S: Subscriber + for<'span> LookupSpan<'span>,
     {
-        let serializer = serde_json::Serializer::new(WriteAdaptor::new(&mut writer));
-        self.format_event(context, serializer, event)?;
+        let writer_adaptor = WriteAdaptor::new(&mut writer);
+
+        if self.pretty {
+            let serializer = serde_json::Serializer::pretty(writer_adaptor);
+            self.format_event(context, serializer, event)?;
+        } else {
+            let serializer = serde_json::Serializer::new(writer_adaptor);
+            self.format_event(context, serializer, event)?;
+        }
+
         writeln!(writer)
     }
 }
@@ -152,8 +426,48 @@ impl Default for EventFormatter {
     fn default() -> Self {
         Self {
             include_source_location: true,
-            #[cfg(feature = "opentelemetry")]
-            cloud_trace_configuration: None,
+            include_target: true,
+            target_key: "target".to_owned(),
+            include_thread_names: false,
+            include_thread_ids: false,
+            include_pid: false,
+            include_hostname: false,
+            include_span_list: true,
+            flatten_spans: false,
+            flattened_span_name_field: "span_name".to_owned(),
+            span_name_key: "name".to_owned(),
+            span_id_fallback: false,
+            nest_dotted_fields: false,
+            field_filter: FieldFilter::default(),
+            max_field_bytes: None,
+            control_char_handling: ControlCharHandling::default(),
+            project_id: None,
+            labels: BTreeMap::new(),
+            resource: None,
+            insert_id_generator: None,
+            severity_mapping: None,
+            trace_severity: TraceSeverity::default(),
+            severity_format: SeverityFormat::default(),
+            log_name_mapper: None,
+            service_context: None,
+            report_errors: false,
+            stack_trace_threshold: None,
+            error_reporting_threshold: LogSeverity::Error,
+            error_reporting_filter: None,
+            error_chain_depth: 10,
+            error_format: ErrorFormat::default(),
+            #[cfg(all(tracing_unstable, feature = "valuable"))]
+            duration_format: crate::DurationFormat::default(),
+            combine_error_messages: false,
+            timestamp_style: TimestampStyle::default(),
+            time_key: None,
+            time_formatter: None,
+            clock: Box::new(SystemClock),
+            pretty: false,
+            message_field: "message".to_owned(),
+            include_empty_messages: true,
+            validate_latency: false,
+            error_handler: None,
         }
     }
 }
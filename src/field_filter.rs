@@ -0,0 +1,55 @@
+use serde_json::Value;
+
+/// Placeholder for the values of redacted fields
+pub(crate) const REDACTED: &str = "[REDACTED]";
+
+/// Fields that are always serialized, regardless of the configured field filters
+const RESERVED_FIELDS: &[&str] = &["message", "severity"];
+
+/// Allowlist and denylist of the event and span fields that are serialized (and of the fields
+/// whose values are redacted), by exact name or by `prefix.*` patterns that match every field
+/// under a dotted prefix
+#[derive(Clone, Debug, Default)]
+pub(crate) struct FieldFilter {
+    pub(crate) included: Option<Vec<String>>,
+    pub(crate) excluded: Vec<String>,
+    pub(crate) redacted: Vec<String>,
+}
+
+impl FieldFilter {
+    /// Whether or not a field should be serialized, with exclusions taking precedence over
+    /// inclusions
+    pub(crate) fn allows(&self, name: &str) -> bool {
+        if RESERVED_FIELDS.contains(&name) {
+            return true;
+        }
+
+        if self.excluded.iter().any(|pattern| matches(pattern, name)) {
+            return false;
+        }
+
+        match &self.included {
+            Some(included) => included.iter().any(|pattern| matches(pattern, name)),
+            None => true,
+        }
+    }
+
+    /// Replace the value of a field with a placeholder if it's redacted (including the entire
+    /// subtree of object values)
+    pub(crate) fn redact(&self, name: &str, value: &mut Value) {
+        if self.redacted.iter().any(|pattern| matches(pattern, name)) {
+            *value = Value::from(REDACTED);
+        }
+    }
+}
+
+/// Whether or not a field name matches an exact name or `prefix.*` pattern, which matches the
+/// prefix itself (e.g. an object field) along with every field under it
+fn matches(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix(".*") {
+        Some(prefix) => name
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.')),
+        None => pattern == name,
+    }
+}
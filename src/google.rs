@@ -1,5 +1,5 @@
 use serde::Serialize;
-use std::{convert::Infallible, fmt, str::FromStr};
+use std::{collections::BTreeMap, convert::Infallible, fmt, str::FromStr};
 use tracing_core::Level;
 
 /// The severity of the event described in a log entry, expressed as standard severity levels.
@@ -8,28 +8,36 @@ use tracing_core::Level;
     all(tracing_unstable, feature = "valuable"),
     derive(valuable::Valuable)
 )]
-#[derive(Debug, Default, Serialize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum LogSeverity {
     /// Log entry has no assigned severity level
     #[default]
-    Default,
+    Default = 0,
     /// Debug or trace information
-    Debug,
+    Debug = 100,
     /// Routine information, such as ongoing status or performance
-    Info,
+    Info = 200,
     /// Normal but significant events, such as start up, shut down, or a configuration change
-    Notice,
+    Notice = 300,
     /// Warning events might cause problems
-    Warning,
+    Warning = 400,
     /// Error events are likely to cause problems
-    Error,
+    Error = 500,
     /// Critical events cause more severe problems or outages
-    Critical,
+    Critical = 600,
     /// A person must take an action immediately
-    Alert,
+    Alert = 700,
     /// One or more systems are unusable
-    Emergency,
+    Emergency = 800,
+}
+
+impl From<LogSeverity> for i32 {
+    /// The numeric value of a LogSeverity, as defined by the `LogSeverity` enum of the Cloud
+    /// Logging API
+    fn from(severity: LogSeverity) -> Self {
+        severity as i32
+    }
 }
 
 impl fmt::Display for LogSeverity {
@@ -61,11 +69,17 @@ impl From<&Level> for LogSeverity {
     }
 }
 
-impl FromStr for LogSeverity {
-    type Err = Infallible;
+impl From<Level> for LogSeverity {
+    fn from(level: Level) -> Self {
+        Self::from(&level)
+    }
+}
 
-    fn from_str(string: &str) -> Result<Self, Self::Err> {
+impl LogSeverity {
+    /// Parse a known LogSeverity name (or tracing Level alias), case-insensitively
+    fn parse(string: &str) -> Option<Self> {
         let severity = match string.to_lowercase().as_str() {
+            "default" => Self::Default,
             "debug" | "trace" => Self::Debug,
             "info" => Self::Info,
             "notice" => Self::Notice,
@@ -74,36 +88,167 @@ impl FromStr for LogSeverity {
             "critical" => Self::Critical,
             "alert" => Self::Alert,
             "emergency" => Self::Emergency,
-            _ => Self::Default,
+            _ => return None,
         };
 
-        Ok(severity)
+        Some(severity)
     }
-}
 
-impl From<serde_json::Value> for LogSeverity {
-    fn from(json: serde_json::Value) -> Self {
+    /// Parse a LogSeverity from a recorded field value, returning `None` for unknown severities
+    pub(crate) fn from_value(json: &serde_json::Value) -> Option<Self> {
         // handle simple string inputs
         if let Some(str) = json.as_str() {
-            return Self::from_str(str).unwrap_or(Self::Default);
+            return Self::parse(str);
         }
 
         // handle wacky object encoding of Valuable enums
         #[cfg(all(tracing_unstable, feature = "valuable"))]
         if let Some(map) = json.as_object() {
             if let Some(key) = map.keys().next() {
-                return Self::from_str(key).unwrap_or(Self::Default);
+                return Self::parse(key);
             }
         }
 
-        Self::Default
+        None
     }
 }
 
-/// Typechecked HttpRequest structure for stucturally logging information about a request.
-/// [See Google's HttpRequest docs here](https://cloud.google.com/logging/docs/reference/v2/rest/v2/LogEntry#HttpRequest).
+impl FromStr for LogSeverity {
+    type Err = Infallible;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        Ok(Self::parse(string).unwrap_or_default())
+    }
+}
+
+impl From<serde_json::Value> for LogSeverity {
+    fn from(json: serde_json::Value) -> Self {
+        Self::from_value(&json).unwrap_or_default()
+    }
+}
+
+/// Representation of the timestamp of each LogEntry, which is [recognized by the logging
+/// agent](https://cloud.google.com/logging/docs/agent/logging/configuration#timestamp-processing)
+/// in a few different forms.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimestampStyle {
+    /// An RFC3339-formatted `time` field
+    #[default]
+    Rfc3339,
+    /// A pair of integer `timestampSeconds` and `timestampNanos` fields, measured since the
+    /// Unix epoch
+    SecondsNanos,
+    /// A `timestamp` object with integer `seconds` and `nanos` fields, measured since the Unix
+    /// epoch
+    Structured,
+}
+
+/// Representation of the `severity` of each LogEntry
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SeverityFormat {
+    /// A `severity` string (e.g. `"INFO"`)
+    #[default]
+    String,
+    /// A numeric `severity` (e.g. `200`)
+    Number,
+    /// A `severity` string alongside a numeric `severityNumber`
+    Both,
+}
+
+/// Representation of `std::error::Error` fields and their chains of sources
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// An object with a combined `message` string and a `causes` list of the error's sources
+    #[default]
+    Structured,
+    /// A multi-line string of the error followed by a `caused by: ` line for each of its sources
+    MultiLine,
+}
+
+/// Representation of `Duration` fields (recorded through `DurationValue` with `valuable` support)
 #[cfg_attr(docsrs, doc(cfg(feature = "valuable")))]
 #[cfg(any(docsrs, all(tracing_unstable, feature = "valuable")))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DurationFormat {
+    /// A fractional number of seconds (e.g. `1.5`)
+    Secs,
+    /// A whole number of milliseconds (e.g. `1500`)
+    #[default]
+    Millis,
+    /// A whole number of microseconds (e.g. `1500000`)
+    Micros,
+    /// A protobuf `Duration` string (e.g. `"1.500000000s"`), like the `latency` of an
+    /// `httpRequest`
+    ProtoString,
+}
+
+/// Treatment of ASCII control characters (other than tabs and line breaks) in string fields,
+/// which some consumers of Cloud Logging entries reject even when escaped
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ControlCharHandling {
+    /// Control characters are kept, and escaped like any other JSON string content (e.g. `\u0000`)
+    #[default]
+    Escape,
+    /// Control characters are removed
+    Strip,
+    /// Control characters are replaced with a given character (e.g. `\u{FFFD}`)
+    Replace(char),
+}
+
+/// LogSeverity of `TRACE`-level Events, which have no direct Cloud Logging equivalent
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TraceSeverity {
+    /// The `DEBUG` LogSeverity, as with `DEBUG`-level Events
+    #[default]
+    Debug,
+    /// The `DEFAULT` LogSeverity, e.g. for keeping `TRACE`-level Events out of `DEBUG` alerts
+    Default,
+}
+
+impl From<TraceSeverity> for LogSeverity {
+    fn from(trace_severity: TraceSeverity) -> Self {
+        match trace_severity {
+            TraceSeverity::Debug => Self::Debug,
+            TraceSeverity::Default => Self::Default,
+        }
+    }
+}
+
+/// Type marker that identifies a LogEntry as an error event for [Error
+/// Reporting](https://cloud.google.com/error-reporting/docs/formatting-error-messages)
+pub(crate) const REPORTED_ERROR_EVENT_TYPE: &str =
+    "type.googleapis.com/google.devtools.clouderrorreporting.v1beta1.ReportedErrorEvent";
+
+/// Identifies the service that reported an error to [Error
+/// Reporting](https://cloud.google.com/error-reporting/docs/formatting-error-messages), which
+/// groups errors by service and version.
+/// [See Google's ServiceContext docs here](https://cloud.google.com/error-reporting/reference/rest/v1beta1/ServiceContext).
+#[derive(Clone, Debug, Serialize)]
+pub struct ServiceContext {
+    /// Identifier of the service, such as the name of the executable or job
+    pub service: String,
+    /// Version of the service, such as a release tag or commit hash
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// Identifies the resource that produced a log entry, for log entries that are shipped without an
+/// agent that attributes them to a resource.
+/// [See Google's MonitoredResource docs here](https://cloud.google.com/logging/docs/reference/v2/rest/v2/MonitoredResource).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct MonitoredResource {
+    /// [Monitored resource type](https://cloud.google.com/logging/docs/api/v2/resource-list) of
+    /// the resource, such as `gce_instance` or `k8s_container`
+    #[serde(rename = "type")]
+    pub resource_type: String,
+    /// Labels that identify the resource, such as `instance_id` and `zone` for `gce_instance`
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Typechecked HttpRequest structure for stucturally logging information about a request.
+/// [See Google's HttpRequest docs here](https://cloud.google.com/logging/docs/reference/v2/rest/v2/LogEntry#HttpRequest).
+#[cfg_attr(docsrs, doc(cfg(feature = "http-request")))]
+#[cfg(any(docsrs, feature = "http-request"))]
 #[derive(Default)]
 pub struct HttpRequest {
     /// Valid HTTP Method for the request (e.g. GET, POST, etc)
@@ -111,9 +256,9 @@ pub struct HttpRequest {
     /// URL from the HTTP request
     pub request_url: Option<url::Url>,
     /// Size of the HTTP request in bytes
-    pub request_size: Option<u32>,
+    pub request_size: Option<u64>,
     /// Size of the HTTP response in bytes
-    pub response_size: Option<u32>,
+    pub response_size: Option<u64>,
     /// Valid HTTP StatusCode for the response
     pub status: Option<http::StatusCode>,
     /// User Agent string of the request
@@ -133,18 +278,335 @@ pub struct HttpRequest {
     /// Whether or not the response was validated with the origin server before being served from cache
     pub cache_validated_with_origin_server: Option<bool>,
     /// Number of HTTP response bytes inserted into cache
-    pub cache_fill_bytes: Option<u32>,
+    pub cache_fill_bytes: Option<u64>,
     /// Protocol used for the request (e.g. "HTTP/1.1", "HTTP/2", "websocket")
     pub protocol: Option<String>,
 }
 
-#[cfg_attr(docsrs, doc(cfg(feature = "valuable")))]
-#[cfg(any(docsrs, all(tracing_unstable, feature = "valuable")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "http-request")))]
+#[cfg(any(docsrs, feature = "http-request"))]
 impl HttpRequest {
     /// Generate a new log-able HttpRequest structured log entry
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Generate a builder for HttpRequest structured log entries, with every field unset
+    pub fn builder() -> HttpRequestBuilder {
+        HttpRequestBuilder::default()
+    }
+
+    /// Generate a new HttpRequest structured log entry from the parts of an HTTP request and
+    /// (optionally) its response, including the method, URL, protocol, `User-Agent` and `Referer`
+    /// headers, and request and response sizes (from `Content-Length` headers) along with the
+    /// response status. Relative request URIs are resolved against the `Host` header, and
+    /// missing or invalid values are left unset.
+    pub fn from_parts(
+        request: &http::request::Parts,
+        response: Option<&http::response::Parts>,
+    ) -> Self {
+        Self {
+            response_size: response
+                .and_then(|response| header(&response.headers, http::header::CONTENT_LENGTH))
+                .and_then(|size| size.parse().ok()),
+            status: response.map(|response| response.status),
+            ..Self::from_request_line(
+                &request.method,
+                &request.uri,
+                request.version,
+                &request.headers,
+            )
+        }
+    }
+
+    /// Complete an HttpRequest structured log entry with the response to its request, setting
+    /// its status, latency, and response size (from the `Content-Length` header, when present)
+    pub fn merge_response<B>(
+        &mut self,
+        response: &http::Response<B>,
+        latency: std::time::Duration,
+    ) {
+        self.status = Some(response.status());
+        self.latency = Some(latency);
+
+        if let Some(response_size) = header(response.headers(), http::header::CONTENT_LENGTH)
+            .and_then(|size| size.parse().ok())
+        {
+            self.response_size = Some(response_size);
+        }
+    }
+
+    /// Request-side fields of an HttpRequest structured log entry
+    fn from_request_line(
+        method: &http::Method,
+        uri: &http::Uri,
+        version: http::Version,
+        headers: &http::HeaderMap,
+    ) -> Self {
+        let request_url = match uri.host() {
+            Some(_) => uri.to_string().parse().ok(),
+            None => header(headers, http::header::HOST).and_then(|host| {
+                let scheme = uri.scheme_str().unwrap_or("http");
+                let path = uri
+                    .path_and_query()
+                    .map(http::uri::PathAndQuery::as_str)
+                    .unwrap_or("/");
+
+                format!("{scheme}://{host}{path}").parse().ok()
+            }),
+        };
+
+        Self {
+            request_method: Some(method.clone()),
+            request_url,
+            request_size: header(headers, http::header::CONTENT_LENGTH)
+                .and_then(|size| size.parse().ok()),
+            user_agent: header(headers, http::header::USER_AGENT).map(ToOwned::to_owned),
+            referer: header(headers, http::header::REFERER).and_then(|url| url.parse().ok()),
+            protocol: Some(format!("{:?}", version)),
+            ..Self::default()
+        }
+    }
+}
+
+/// Generate a new HttpRequest structured log entry from an HTTP request, like
+/// [`HttpRequest::from_parts`] without a response
+#[cfg_attr(docsrs, doc(cfg(feature = "http-request")))]
+#[cfg(any(docsrs, feature = "http-request"))]
+impl<B> From<&http::Request<B>> for HttpRequest {
+    fn from(request: &http::Request<B>) -> Self {
+        Self::from_request_line(
+            request.method(),
+            request.uri(),
+            request.version(),
+            request.headers(),
+        )
+    }
+}
+
+/// Fields of an HttpRequest in the representation of the `httpRequest` of a LogEntry, shared by
+/// structured HttpRequests and the dotted fields of `http_request_event!`
+#[doc(hidden)]
+#[cfg(feature = "http-request")]
+#[derive(Default)]
+pub struct HttpRequestFields<'a> {
+    pub request_method: Option<&'a str>,
+    pub request_url: Option<&'a str>,
+    pub request_size: Option<String>,
+    pub response_size: Option<String>,
+    pub status: Option<u16>,
+    pub user_agent: Option<&'a str>,
+    pub remote_ip: Option<String>,
+    pub server_ip: Option<String>,
+    pub referer: Option<&'a str>,
+    pub latency: Option<String>,
+    pub cache_lookup: Option<bool>,
+    pub cache_hit: Option<bool>,
+    pub cache_validated_with_origin_server: Option<bool>,
+    pub cache_fill_bytes: Option<String>,
+    pub protocol: Option<&'a str>,
+}
+
+#[cfg(feature = "http-request")]
+impl<'a> HttpRequestFields<'a> {
+    pub fn new(http_request: &'a HttpRequest) -> Self {
+        Self {
+            request_method: http_request
+                .request_method
+                .as_ref()
+                .map(http::Method::as_str),
+            request_url: http_request.request_url.as_ref().map(url::Url::as_str),
+            // int64 fields are written as strings in the JSON form of LogEntry
+            request_size: http_request.request_size.map(|size| size.to_string()),
+            response_size: http_request.response_size.map(|size| size.to_string()),
+            status: http_request.status.map(|status| status.as_u16()),
+            user_agent: http_request.user_agent.as_deref(),
+            remote_ip: http_request.remote_ip.map(|ip| ip.to_string()),
+            server_ip: http_request.server_ip.map(|ip| ip.to_string()),
+            referer: http_request.referer.as_ref().map(url::Url::as_str),
+            latency: http_request.latency.map(format_duration),
+            cache_lookup: http_request.cache_lookup,
+            cache_hit: http_request.cache_hit,
+            cache_validated_with_origin_server: http_request.cache_validated_with_origin_server,
+            cache_fill_bytes: http_request.cache_fill_bytes.map(|size| size.to_string()),
+            protocol: http_request.protocol.as_deref(),
+        }
+    }
+}
+
+/// Emits an Event (like `tracing::event!`) with the fields of an [`HttpRequest`] as dotted
+/// `http_request.*` fields, which are written to the `httpRequest` of the LogEntry exactly like
+/// a structured `HttpRequest` recorded through `valuable` (without requiring
+/// `--cfg tracing_unstable`). Additional fields and the message follow the HttpRequest, e.g.
+/// `http_request_event!(Level::INFO, http_request, user_id = 42, "Request handled")`.
+#[cfg_attr(docsrs, doc(cfg(feature = "http-request")))]
+#[cfg(any(docsrs, feature = "http-request"))]
+#[macro_export]
+macro_rules! http_request_event {
+    ($level:expr, $http_request:expr, $($arg:tt)+) => {{
+        let http_request = $crate::__private::HttpRequestFields::new(&$http_request);
+
+        $crate::__private::tracing::event!(
+            $level,
+            http_request.request_method = http_request.request_method,
+            http_request.request_url = http_request.request_url,
+            http_request.request_size = http_request.request_size.as_deref(),
+            http_request.response_size = http_request.response_size.as_deref(),
+            http_request.status = http_request.status,
+            http_request.user_agent = http_request.user_agent,
+            http_request.remote_ip = http_request.remote_ip.as_deref(),
+            http_request.server_ip = http_request.server_ip.as_deref(),
+            http_request.referer = http_request.referer,
+            http_request.latency = http_request.latency.as_deref(),
+            http_request.cache_lookup = http_request.cache_lookup,
+            http_request.cache_hit = http_request.cache_hit,
+            http_request.cache_validated_with_origin_server =
+                http_request.cache_validated_with_origin_server,
+            http_request.cache_fill_bytes = http_request.cache_fill_bytes.as_deref(),
+            http_request.protocol = http_request.protocol,
+            $($arg)+
+        )
+    }};
+}
+
+/// Value of an HTTP header, skipping values that aren't valid UTF-8
+#[cfg(feature = "http-request")]
+fn header(headers: &http::HeaderMap, name: http::header::HeaderName) -> Option<&str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// Representation of a `Duration` in a given DurationFormat
+#[cfg(all(tracing_unstable, feature = "valuable"))]
+pub(crate) fn duration_value(
+    duration: std::time::Duration,
+    duration_format: DurationFormat,
+) -> serde_json::Value {
+    match duration_format {
+        DurationFormat::Secs => duration.as_secs_f64().into(),
+        DurationFormat::Millis => u64::try_from(duration.as_millis())
+            .unwrap_or(u64::MAX)
+            .into(),
+        DurationFormat::Micros => u64::try_from(duration.as_micros())
+            .unwrap_or(u64::MAX)
+            .into(),
+        DurationFormat::ProtoString => format_duration(duration).into(),
+    }
+}
+
+/// Canonical protobuf JSON representation of a `Duration` (e.g. `1.234000000s`), which avoids
+/// both the rounding and the scientific notation of floating-point formatting
+pub(crate) fn format_duration(duration: std::time::Duration) -> String {
+    format!("{}.{:09}s", duration.as_secs(), duration.subsec_nanos())
+}
+
+/// Builder for [`HttpRequest`] structured log entries
+#[cfg_attr(docsrs, doc(cfg(feature = "http-request")))]
+#[cfg(any(docsrs, feature = "http-request"))]
+#[derive(Default)]
+#[must_use = "builders do nothing unless `build` is called"]
+pub struct HttpRequestBuilder(HttpRequest);
+
+#[cfg_attr(docsrs, doc(cfg(feature = "http-request")))]
+#[cfg(any(docsrs, feature = "http-request"))]
+impl HttpRequestBuilder {
+    /// Sets the HTTP Method for the request (e.g. GET, POST, etc)
+    pub fn request_method(mut self, request_method: http::Method) -> Self {
+        self.0.request_method = Some(request_method);
+        self
+    }
+
+    /// Sets the URL from the HTTP request
+    pub fn request_url(mut self, request_url: url::Url) -> Self {
+        self.0.request_url = Some(request_url);
+        self
+    }
+
+    /// Sets the size of the HTTP request in bytes
+    pub fn request_size(mut self, request_size: u64) -> Self {
+        self.0.request_size = Some(request_size);
+        self
+    }
+
+    /// Sets the size of the HTTP response in bytes
+    pub fn response_size(mut self, response_size: u64) -> Self {
+        self.0.response_size = Some(response_size);
+        self
+    }
+
+    /// Sets the HTTP StatusCode for the response
+    pub fn status(mut self, status: http::StatusCode) -> Self {
+        self.0.status = Some(status);
+        self
+    }
+
+    /// Sets the User Agent string of the request
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.0.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sets the IP address of the client that issued the request
+    pub fn remote_ip(mut self, remote_ip: std::net::IpAddr) -> Self {
+        self.0.remote_ip = Some(remote_ip);
+        self
+    }
+
+    /// Sets the IP address of the server that the request was sent to
+    pub fn server_ip(mut self, server_ip: std::net::IpAddr) -> Self {
+        self.0.server_ip = Some(server_ip);
+        self
+    }
+
+    /// Sets the referer URL of the request
+    pub fn referer(mut self, referer: url::Url) -> Self {
+        self.0.referer = Some(referer);
+        self
+    }
+
+    /// Sets the processing latency on the server
+    pub fn latency(mut self, latency: std::time::Duration) -> Self {
+        self.0.latency = Some(latency);
+        self
+    }
+
+    /// Sets whether or not a cache lookup was attempted
+    pub fn cache_lookup(mut self, cache_lookup: bool) -> Self {
+        self.0.cache_lookup = Some(cache_lookup);
+        self
+    }
+
+    /// Sets whether or not an entity was served from cache
+    pub fn cache_hit(mut self, cache_hit: bool) -> Self {
+        self.0.cache_hit = Some(cache_hit);
+        self
+    }
+
+    /// Sets whether or not the response was validated with the origin server before being served from cache
+    pub fn cache_validated_with_origin_server(
+        mut self,
+        cache_validated_with_origin_server: bool,
+    ) -> Self {
+        self.0.cache_validated_with_origin_server = Some(cache_validated_with_origin_server);
+        self
+    }
+
+    /// Sets the number of HTTP response bytes inserted into cache
+    pub fn cache_fill_bytes(mut self, cache_fill_bytes: u64) -> Self {
+        self.0.cache_fill_bytes = Some(cache_fill_bytes);
+        self
+    }
+
+    /// Sets the protocol used for the request (e.g. "HTTP/1.1", "HTTP/2", "websocket")
+    pub fn protocol(mut self, protocol: impl Into<String>) -> Self {
+        self.0.protocol = Some(protocol.into());
+        self
+    }
+
+    /// Builds the HttpRequest
+    #[must_use]
+    pub fn build(self) -> HttpRequest {
+        self.0
+    }
 }
 
 #[cfg(all(tracing_unstable, feature = "valuable"))]
@@ -174,45 +636,72 @@ impl valuable::Valuable for HttpRequest {
     }
 
     fn visit(&self, visit: &mut dyn valuable::Visit) {
-        let request_method = self
-            .request_method
-            .as_ref()
-            .map(|method| method.to_string());
-        let request_url = self.request_url.as_ref().map(|url| url.to_string());
-        let status = self.status.map(|status| status.as_u16());
-        let user_agent = &self.user_agent;
-        let remote_ip = self.remote_ip.map(|ip| ip.to_string());
-        let server_ip = self.server_ip.map(|ip| ip.to_string());
-        let referer = self.referer.as_ref().map(|url| url.to_string());
-        let latency = self
-            .latency
-            .map(|latency| format!("{}s", latency.as_secs_f32()));
+        let http_request = HttpRequestFields::new(self);
 
         let (fields, values): (Vec<_>, Vec<_>) = HTTP_REQUEST_FIELDS
             .iter()
             .zip(
                 [
-                    request_method.as_ref().map(valuable::Valuable::as_value),
-                    request_url.as_ref().map(valuable::Valuable::as_value),
-                    self.request_size.as_ref().map(valuable::Valuable::as_value),
-                    self.response_size
+                    http_request
+                        .request_method
+                        .as_ref()
+                        .map(valuable::Valuable::as_value),
+                    http_request
+                        .request_url
+                        .as_ref()
+                        .map(valuable::Valuable::as_value),
+                    http_request
+                        .request_size
+                        .as_ref()
+                        .map(valuable::Valuable::as_value),
+                    http_request
+                        .response_size
+                        .as_ref()
+                        .map(valuable::Valuable::as_value),
+                    http_request
+                        .status
+                        .as_ref()
+                        .map(valuable::Valuable::as_value),
+                    http_request
+                        .user_agent
+                        .as_ref()
+                        .map(valuable::Valuable::as_value),
+                    http_request
+                        .remote_ip
+                        .as_ref()
+                        .map(valuable::Valuable::as_value),
+                    http_request
+                        .server_ip
+                        .as_ref()
+                        .map(valuable::Valuable::as_value),
+                    http_request
+                        .referer
+                        .as_ref()
+                        .map(valuable::Valuable::as_value),
+                    http_request
+                        .latency
+                        .as_ref()
+                        .map(valuable::Valuable::as_value),
+                    http_request
+                        .cache_lookup
+                        .as_ref()
+                        .map(valuable::Valuable::as_value),
+                    http_request
+                        .cache_hit
+                        .as_ref()
+                        .map(valuable::Valuable::as_value),
+                    http_request
+                        .cache_validated_with_origin_server
                         .as_ref()
                         .map(valuable::Valuable::as_value),
-                    status.as_ref().map(valuable::Valuable::as_value),
-                    user_agent.as_ref().map(valuable::Valuable::as_value),
-                    remote_ip.as_ref().map(valuable::Valuable::as_value),
-                    server_ip.as_ref().map(valuable::Valuable::as_value),
-                    referer.as_ref().map(valuable::Valuable::as_value),
-                    latency.as_ref().map(valuable::Valuable::as_value),
-                    self.cache_lookup.as_ref().map(valuable::Valuable::as_value),
-                    self.cache_hit.as_ref().map(valuable::Valuable::as_value),
-                    self.cache_validated_with_origin_server
+                    http_request
+                        .cache_fill_bytes
                         .as_ref()
                         .map(valuable::Valuable::as_value),
-                    self.cache_fill_bytes
+                    http_request
+                        .protocol
                         .as_ref()
                         .map(valuable::Valuable::as_value),
-                    self.protocol.as_ref().map(valuable::Valuable::as_value),
                 ]
                 .iter(),
             )
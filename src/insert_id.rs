@@ -0,0 +1,31 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Per-process counter for generated insert IDs
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Per-process prefix for generated insert IDs, derived from the process start time and ID
+static PREFIX: OnceLock<String> = OnceLock::new();
+
+/// Generate the next insert ID for this process. Insert IDs are fixed-width so that their
+/// lexicographic order (used by Cloud Logging to order entries with identical timestamps)
+/// matches the order in which they were generated.
+pub(crate) fn next() -> String {
+    let prefix = PREFIX.get_or_init(|| {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+
+        format!("{:016x}{:08x}", started, std::process::id())
+    });
+
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    format!("{prefix}-{count:016x}")
+}
@@ -0,0 +1,72 @@
+use valuable::{Listable, Mappable, Valuable, Value, Visit};
+
+/// `valuable`-compatible wrapper for recording a pre-built `serde_json::Value` as a field, which
+/// is serialized verbatim (e.g. objects as nested objects rather than strings)
+#[cfg_attr(docsrs, doc(cfg(feature = "valuable")))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JsonValue<'a>(&'a serde_json::Value);
+
+impl<'a> JsonValue<'a> {
+    /// Wrap a JSON value for recording as a field
+    pub fn new(value: &'a serde_json::Value) -> Self {
+        Self(value)
+    }
+}
+
+impl<'a> From<&'a serde_json::Value> for JsonValue<'a> {
+    fn from(value: &'a serde_json::Value) -> Self {
+        Self(value)
+    }
+}
+
+impl<'a> Valuable for JsonValue<'a> {
+    fn as_value(&self) -> Value<'_> {
+        match self.0 {
+            serde_json::Value::Null => Value::Unit,
+            serde_json::Value::Bool(value) => Value::Bool(*value),
+            serde_json::Value::Number(number) => match (number.as_u64(), number.as_i64()) {
+                (Some(number), _) => Value::U64(number),
+                (None, Some(number)) => Value::I64(number),
+                // every other JSON number is representable as a float
+                (None, None) => Value::F64(number.as_f64().unwrap_or_default()),
+            },
+            serde_json::Value::String(value) => Value::String(value),
+            serde_json::Value::Array(_) => Value::Listable(self),
+            serde_json::Value::Object(_) => Value::Mappable(self),
+        }
+    }
+
+    fn visit(&self, visit: &mut dyn Visit) {
+        match self.0 {
+            serde_json::Value::Array(values) => {
+                for value in values {
+                    visit.visit_value(JsonValue(value).as_value());
+                }
+            }
+            serde_json::Value::Object(entries) => {
+                for (key, value) in entries {
+                    visit.visit_entry(key.as_value(), JsonValue(value).as_value());
+                }
+            }
+            _ => visit.visit_value(self.as_value()),
+        }
+    }
+}
+
+impl<'a> Listable for JsonValue<'a> {
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let length = self.0.as_array().map(Vec::len).unwrap_or_default();
+        (length, Some(length))
+    }
+}
+
+impl<'a> Mappable for JsonValue<'a> {
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let length = self
+            .0
+            .as_object()
+            .map(serde_json::Map::len)
+            .unwrap_or_default();
+        (length, Some(length))
+    }
+}
@@ -1,9 +1,32 @@
-use crate::event_formatter::EventFormatter;
-use std::{fmt, io, ops::Deref};
-use tracing_core::{Event, Subscriber};
+use crate::{
+    builder::StackdriverBuilder,
+    clock::Clock,
+    event_formatter::EventFormatter,
+    google::{
+        ControlCharHandling, ErrorFormat, LogSeverity, MonitoredResource, ServiceContext,
+        SeverityFormat, TimestampStyle, TraceSeverity,
+    },
+    serializers::cache_span_fields,
+    span_events::{with_span_event, Opened, SpanConfiguration, SpanEvents, Timings},
+    trace_context::WithContext,
+    writer::{BufferWriter, ErrorHandler, WithErrorHandler},
+};
+use std::{
+    fmt, io,
+    ops::Deref,
+    sync::{Arc, Mutex},
+};
+use time::OffsetDateTime;
+use tracing_core::{Event, Level, Metadata, Subscriber};
 use tracing_subscriber::{
-    fmt::{format::JsonFields, MakeWriter},
+    fmt::{
+        format::JsonFields,
+        writer::{MakeWriterExt, OrElse, WithMaxLevel},
+        MakeWriter,
+    },
+    layer::SubscriberExt,
     registry::LookupSpan,
+    Registry,
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -24,25 +47,45 @@ impl From<Error> for fmt::Error {
     }
 }
 
-/// Create a configurable stackdriver-specific Layer and event formatter
+/// Create a configurable stackdriver-specific Layer and event formatter, which is equivalent to
+/// `Stackdriver::builder().build()`
 pub fn layer<S>() -> Layer<S>
 where
     S: Subscriber + for<'span> LookupSpan<'span>,
 {
-    Layer(
-        tracing_subscriber::fmt::layer()
-            .json()
-            .event_format(EventFormatter::default()),
-    )
+    Layer::builder().build()
 }
 
+/// Alias of [`Layer`], e.g. for building one with `Stackdriver::builder()`
+pub type Stackdriver<S, W = fn() -> io::Stdout> = Layer<S, W>;
+
 /// A tracing-compatible Layer implementation for Stackdriver
 pub struct Layer<S, W = fn() -> io::Stdout>(
-    tracing_subscriber::fmt::Layer<S, JsonFields, EventFormatter, W>,
+    tracing_subscriber::fmt::Layer<S, JsonFields, EventFormatter, WithErrorHandler<W>>,
+    SpanConfiguration,
 )
 where
     S: Subscriber + for<'span> LookupSpan<'span>;
 
+impl<S> Layer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    /// Create a builder for a Layer with the default configuration (writing to `stdout`)
+    pub fn builder() -> StackdriverBuilder<S> {
+        StackdriverBuilder::new(Layer(
+            tracing_subscriber::fmt::layer()
+                .json()
+                .event_format(EventFormatter::default())
+                .with_writer(WithErrorHandler::new(
+                    io::stdout as fn() -> io::Stdout,
+                    None,
+                )),
+            SpanConfiguration::default(),
+        ))
+    }
+}
+
 impl<S, W> Layer<S, W>
 where
     S: Subscriber + for<'span> LookupSpan<'span>,
@@ -53,25 +96,672 @@ where
     where
         M: for<'writer> MakeWriter<'writer> + 'static,
     {
-        Layer(self.0.with_writer(make_writer))
+        // writers keep the error handler of the Layer, whenever it was configured
+        let handler = self.0.writer().handler();
+
+        Layer(
+            self.0
+                .with_writer(WithErrorHandler::new(make_writer, handler)),
+            self.1,
+        )
     }
 
-    /// Configures whether or not Events will include source locations in a special LogEntry field
+    /// Sets a non-blocking writer for `stdout`, which writes Events from a dedicated worker thread
+    /// instead of the thread that emitted them (e.g. an async runtime's worker threads). Events are
+    /// only guaranteed to be written while the returned `WorkerGuard` is held, so it should be kept
+    /// alive until the end of `main`.
+    #[cfg_attr(docsrs, doc(cfg(feature = "non-blocking")))]
+    #[cfg(any(docsrs, feature = "non-blocking"))]
+    pub fn non_blocking(
+        self,
+    ) -> (
+        Layer<S, tracing_appender::non_blocking::NonBlocking>,
+        tracing_appender::non_blocking::WorkerGuard,
+    ) {
+        let (make_writer, guard) = tracing_appender::non_blocking(io::stdout());
+
+        (self.with_writer(make_writer), guard)
+    }
+
+    /// Configures a handler for the errors of the Layer's writers (e.g. a broken `stdout` pipe),
+    /// which replaces the default diagnostic written to `stderr`. Events that can't be written are
+    /// dropped without interrupting the process either way. The handler also receives warnings
+    /// (as `InvalidData` errors) about Events that were written despite invalid field values, e.g.
+    /// unrecognized `severity` overrides, which are discarded without a handler. The handler
+    /// applies to every MakeWriter of the Layer, whether it's set before or after this method.
+    pub fn on_error<F>(self, handler: F) -> Self
+    where
+        F: Fn(io::Error) + Send + Sync + 'static,
+    {
+        let handler: ErrorHandler = Arc::new(handler);
+        let formatter_handler = handler.clone();
+
+        Layer(
+            self.0
+                .map_writer(|make_writer| make_writer.with_handler(handler))
+                .map_event_format(|mut event_formatter| {
+                    event_formatter.error_handler = Some(formatter_handler);
+                    event_formatter
+                }),
+            self.1,
+        )
+    }
+
+    /// Sets separate MakeWriters for Events at or above the `WARN` Level (e.g. `std::io::stderr`)
+    /// and for all other Events (e.g. `std::io::stdout`)
+    pub fn with_split_writers<O, E>(
+        self,
+        make_writer: O,
+        make_error_writer: E,
+    ) -> Layer<S, OrElse<WithMaxLevel<E>, O>>
+    where
+        O: for<'writer> MakeWriter<'writer> + 'static,
+        E: for<'writer> MakeWriter<'writer> + 'static,
+    {
+        self.with_split_writers_at(Level::WARN, make_writer, make_error_writer)
+    }
+
+    /// Sets separate MakeWriters for Events at or above the provided `threshold` Level and for all
+    /// other Events
+    pub fn with_split_writers_at<O, E>(
+        self,
+        threshold: Level,
+        make_writer: O,
+        make_error_writer: E,
+    ) -> Layer<S, OrElse<WithMaxLevel<E>, O>>
+    where
+        O: for<'writer> MakeWriter<'writer> + 'static,
+        E: for<'writer> MakeWriter<'writer> + 'static,
+    {
+        self.with_writer(
+            make_error_writer
+                .with_max_level(threshold)
+                .or_else(make_writer),
+        )
+    }
+
+    /// Configures whether or not Events will include source locations in a special LogEntry field.
+    /// Source locations include the `file`, `line`, and `function` (i.e. module path) of an
+    /// Event's callsite, and are omitted for Events whose metadata lacks a file.
     pub fn with_source_location(self, include_source_location: bool) -> Self {
-        Self(self.0.map_event_format(|mut event_formatter| {
+        self.map_event_format(|mut event_formatter| {
             event_formatter.include_source_location = include_source_location;
             event_formatter
-        }))
+        })
+    }
+
+    /// Configures whether or not Events will include a `target` field (derived from the Event's
+    /// `target` metadata, which is often the same as its module path)
+    pub fn with_target(self, include_target: bool) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.include_target = include_target;
+            event_formatter
+        })
+    }
+
+    /// Configures the key of the field that each Event's target is written to (`target` by
+    /// default), e.g. `logger`
+    pub fn with_target_key(self, target_key: impl Into<String>) -> Self {
+        let target_key = target_key.into();
+
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.target_key = target_key;
+            event_formatter
+        })
+    }
+
+    /// Configures whether or not Events will include a `thread.name` field with the name of the
+    /// thread that emitted them (which is omitted for unnamed threads)
+    pub fn with_thread_names(self, include_thread_names: bool) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.include_thread_names = include_thread_names;
+            event_formatter
+        })
+    }
+
+    /// Configures whether or not Events will include a `thread.id` field with the ID of the thread
+    /// that emitted them (e.g. `ThreadId(1)`)
+    pub fn with_thread_ids(self, include_thread_ids: bool) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.include_thread_ids = include_thread_ids;
+            event_formatter
+        })
+    }
+
+    /// Configures whether or not Events will include the ID of the current process as a numeric
+    /// `process.pid` field
+    pub fn with_pid(self, include_pid: bool) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.include_pid = include_pid;
+            event_formatter
+        })
+    }
+
+    /// Configures whether or not Events will include the name of the current host as a
+    /// `host.name` field, which is resolved once when the first Event is formatted
+    pub fn with_hostname(self, include_hostname: bool) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.include_hostname = include_hostname;
+            event_formatter
+        })
+    }
+
+    /// Configures whether or not Events within spans will include a `spans` list of the name and
+    /// fields of every span in the Event's scope, ordered from the root span to the current span,
+    /// alongside the current `span`
+    pub fn with_span_list(self, include_span_list: bool) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.include_span_list = include_span_list;
+            event_formatter
+        })
+    }
+
+    /// Configures whether or not the fields of the current span will be merged into the root of
+    /// each Event (instead of being nested under a `span` key), with Event fields taking
+    /// precedence over span fields of the same name. The span's name is included under a
+    /// `spanName` key, which can be changed with
+    /// [`with_flattened_span_name_field`](Self::with_flattened_span_name_field).
+    pub fn with_flattened_spans(self, flatten_spans: bool) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.flatten_spans = flatten_spans;
+            event_formatter
+        })
+    }
+
+    /// Configures whether or not Event fields with dotted names (e.g. `db.connection.pool.size`)
+    /// are written as nested objects (e.g. `{"db": {"connection": {"pool": {"size": 3}}}}`)
+    /// rather than as single camelCased keys (e.g. `dbConnectionPoolSize`). Sibling fields share
+    /// their parent objects, and nested objects take precedence over other fields at the same
+    /// path (with a warning passed to the Layer's `on_error` handler). Well-known prefixes like
+    /// `http_request` and `labels` are handled as usual.
+    pub fn with_nested_fields(self, nest_dotted_fields: bool) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.nest_dotted_fields = nest_dotted_fields;
+            event_formatter
+        })
+    }
+
+    /// Configures an allowlist of the event and span fields that are serialized, by exact name
+    /// (e.g. `user_id`) or by `prefix.*` patterns (e.g. `http_request.*`). The `message` and
+    /// `severity` fields are always serialized, and exclusions configured with
+    /// [`with_excluded_fields`](Self::with_excluded_fields) take precedence.
+    pub fn with_included_fields<I, F>(self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = F>,
+        F: Into<String>,
+    {
+        let fields = fields.into_iter().map(Into::into).collect();
+
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.field_filter.included = Some(fields);
+            event_formatter
+        })
+    }
+
+    /// Configures a denylist of the event and span fields that are never serialized (e.g. fields
+    /// with personal data), by exact name (e.g. `email`) or by `prefix.*` patterns (e.g.
+    /// `user.*`). The `message` and `severity` fields are always serialized.
+    pub fn with_excluded_fields<I, F>(self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = F>,
+        F: Into<String>,
+    {
+        let fields = fields.into_iter().map(Into::into).collect();
+
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.field_filter.excluded = fields;
+            event_formatter
+        })
+    }
+
+    /// Configures the event and span fields whose values are replaced with a `[REDACTED]`
+    /// placeholder (e.g. `password`), keeping their keys in place. Fields are matched by exact
+    /// name or by `prefix.*` patterns (e.g. `auth.*`), which also match object fields named after
+    /// the prefix as a whole.
+    pub fn with_redacted_fields<I, F>(self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = F>,
+        F: Into<String>,
+    {
+        let fields = fields.into_iter().map(Into::into).collect();
+
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.field_filter.redacted = fields;
+            event_formatter
+        })
+    }
+
+    /// Configures the maximum size in bytes of string event fields (including the message), or
+    /// disables truncation altogether with `None` (the default). Longer values (including
+    /// combined error messages) are truncated to end with a `…` marker within the limit and
+    /// flagged with a sibling `<field>Truncated: true` field, so that an oversized field doesn't
+    /// push an entry past Cloud Logging's size limit. Fields that are mapped to special LogEntry
+    /// fields (e.g. `trace_id`) are never truncated.
+    pub fn with_max_field_bytes(self, max_field_bytes: Option<usize>) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.max_field_bytes = max_field_bytes;
+            event_formatter
+        })
+    }
+
+    /// Configures the treatment of ASCII control characters other than tabs and line breaks (e.g.
+    /// `NUL` or backspace) in string event fields, which are escaped by default
+    pub fn with_control_char_handling(self, control_char_handling: ControlCharHandling) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.control_char_handling = control_char_handling;
+            event_formatter
+        })
+    }
+
+    /// Configures the key of the field that the current span's name is written to when span
+    /// fields are flattened (`span_name` by default, camelCased like any other field)
+    pub fn with_flattened_span_name_field(self, span_name_field: impl Into<String>) -> Self {
+        let span_name_field = span_name_field.into();
+
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.flattened_span_name_field = span_name_field;
+            event_formatter
+        })
+    }
+
+    /// Configures the key that the names of spans are written to within the `span` and `spans`
+    /// objects (`name` by default). Span fields that share this key are written to a
+    /// `<key>Value` field instead (like colliding event fields), so that neither clobbers the
+    /// other.
+    pub fn with_span_name_key(self, span_name_key: impl Into<String>) -> Self {
+        let span_name_key = span_name_key.into();
+
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.span_name_key = span_name_key;
+            event_formatter
+        })
+    }
+
+    /// Configures whether or not Events within spans that lack a `span_id` field will fall back to
+    /// the `tracing` ID of their span for the special `logging.googleapis.com/spanId` LogEntry
+    /// field, which groups entries by span without a full Cloud Trace integration. Since `tracing`
+    /// span IDs are only unique within a process (and unrelated to Cloud Trace spans), this is
+    /// disabled by default.
+    pub fn with_span_id_fallback(self, span_id_fallback: bool) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.span_id_fallback = span_id_fallback;
+            event_formatter
+        })
+    }
+
+    /// Configures the Google Cloud project ID used to qualify `trace_id` fields as Cloud Trace
+    /// resource names in the special `logging.googleapis.com/trace` LogEntry field. Without a
+    /// project ID, `trace_id` fields are passed through to that LogEntry field verbatim.
+    pub fn with_project_id(self, project_id: impl Into<String>) -> Self {
+        let project_id = project_id.into();
+
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.project_id = Some(project_id);
+            event_formatter
+        })
+    }
+
+    /// Configures the Google Cloud project ID used for `trace_id` fields (see
+    /// [`with_project_id`](Self::with_project_id)) by detecting it once from the
+    /// `GOOGLE_CLOUD_PROJECT` or `GCP_PROJECT` environment variables and, with the `metadata`
+    /// feature, the metadata server. If no project ID can be detected (e.g. when running
+    /// locally), the layer is left unchanged.
+    pub fn with_auto_project_id(self) -> Self {
+        match crate::environment::detect_project_id() {
+            Some(project_id) => self.with_project_id(project_id),
+            None => self,
+        }
+    }
+
+    /// Configures the MonitoredResource that is included in the `resource` field of every Event,
+    /// which attributes Events to the right resource when logs are shipped to Cloud Logging without
+    /// an agent that injects the resource itself
+    pub fn with_resource(self, resource: MonitoredResource) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.resource = Some(resource);
+            event_formatter
+        })
+    }
+
+    /// Configures static labels that are included in the special `logging.googleapis.com/labels`
+    /// LogEntry field of every Event, alongside any `labels.`-prefixed Event fields. Labels from
+    /// Event (and span) fields take precedence over static labels with the same key.
+    pub fn with_labels<K, V>(self, labels: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: ToString,
+    {
+        let labels = labels
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.to_string()));
+
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.labels.extend(labels);
+            event_formatter
+        })
+    }
+
+    /// Configures whether or not Events without an `insert_id` field will be assigned a generated,
+    /// monotonically-increasing ID in the special `logging.googleapis.com/insertId` LogEntry field
+    pub fn with_auto_insert_id(self, auto_insert_id: bool) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.insert_id_generator = if auto_insert_id {
+                Some(Box::new(|_| crate::insert_id::next()))
+            } else {
+                None
+            };
+            event_formatter
+        })
+    }
+
+    /// Configures a generator for the special `logging.googleapis.com/insertId` LogEntry field of
+    /// Events without an `insert_id` field, e.g. for deriving deterministic IDs from request IDs
+    pub fn with_insert_id_generator<F>(self, insert_id_generator: F) -> Self
+    where
+        F: Fn(&Event<'_>) -> String + Send + Sync + 'static,
+    {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.insert_id_generator = Some(Box::new(insert_id_generator));
+            event_formatter
+        })
+    }
+
+    /// Configures the mapping from tracing Levels to the LogSeverity of Events, replacing the
+    /// default mapping (see `LogSeverity`'s `From<&Level>` implementation)
+    pub fn with_severity_mapping<F>(self, severity_mapping: F) -> Self
+    where
+        F: Fn(&Level) -> LogSeverity + Send + Sync + 'static,
+    {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.severity_mapping = Some(Box::new(severity_mapping));
+            event_formatter
+        })
+    }
+
+    /// Configures the LogSeverity of `TRACE`-level Events (`DEBUG` by default). Any custom
+    /// mapping configured with [`with_severity_mapping`](Self::with_severity_mapping) takes
+    /// precedence.
+    pub fn with_trace_severity(self, trace_severity: TraceSeverity) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.trace_severity = trace_severity;
+            event_formatter
+        })
+    }
+
+    /// Configures the representation of Event severities (a `severity` string by default), e.g.
+    /// for consumers that expect the numeric LogSeverity values of the Cloud Logging API
+    pub fn with_severity_format(self, severity_format: SeverityFormat) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.severity_format = severity_format;
+            event_formatter
+        })
+    }
+
+    /// Configures a mapping from Event metadata (e.g. targets or levels) to a custom log name in a
+    /// `logging.googleapis.com/logName` field, for log routers that write entries to different
+    /// logs. Events mapped to `None` are left without a custom log name.
+    pub fn with_log_name_mapper<F>(self, log_name_mapper: F) -> Self
+    where
+        F: Fn(&Metadata<'_>) -> Option<String> + Send + Sync + 'static,
+    {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.log_name_mapper = Some(Box::new(log_name_mapper));
+            event_formatter
+        })
+    }
+
+    /// Configures the [Error Reporting](https://cloud.google.com/error-reporting) integration,
+    /// which marks Events at or above the error reporting threshold (`LogSeverity::Error` by
+    /// default) as error events for the provided service
+    pub fn with_error_reporting(self, service_context: ServiceContext) -> Self {
+        // service contexts are static, so they're serialized once rather than for every Event
+        let service_context = serde_json::value::to_raw_value(&service_context)
+            .expect("ServiceContexts should always serialize");
+
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.service_context = Some(service_context);
+            event_formatter
+        })
+    }
+
+    /// Configures whether or not Events at or above the error reporting threshold are marked as
+    /// error events for [Error Reporting](https://cloud.google.com/error-reporting) through an
+    /// `@type` field, without a `serviceContext` (which Error Reporting then derives from the
+    /// resource that wrote the log entry). Events with their own `@type` field are left as-is.
+    pub fn with_report_errors(self, report_errors: bool) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.report_errors = report_errors;
+            event_formatter
+        })
+    }
+
+    /// Configures the minimum LogSeverity of Events that are marked as error events when the
+    /// [Error Reporting](https://cloud.google.com/error-reporting) integration is enabled
+    pub fn with_error_reporting_threshold(self, threshold: LogSeverity) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.error_reporting_threshold = threshold;
+            event_formatter
+        })
+    }
+
+    /// Configures a filter that decides which Events are marked as error events when the
+    /// [Error Reporting](https://cloud.google.com/error-reporting) integration is enabled, based
+    /// on each Event and its LogSeverity, replacing the error reporting threshold (e.g. to report
+    /// Events with an `error` field at any severity, or to skip noisy targets). Only the Error
+    /// Reporting fields of Events are affected, never their severities.
+    pub fn with_error_reporting_filter<F>(self, error_reporting_filter: F) -> Self
+    where
+        F: Fn(&Event<'_>, LogSeverity) -> bool + Send + Sync + 'static,
+    {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.error_reporting_filter = Some(Box::new(error_reporting_filter));
+            event_formatter
+        })
+    }
+
+    /// Configures the representation of `Duration` fields recorded through `DurationValue` (a
+    /// whole number of milliseconds by default), e.g. as numbers for dashboards or as protobuf
+    /// `Duration` strings
+    #[cfg_attr(docsrs, doc(cfg(feature = "valuable")))]
+    #[cfg(any(docsrs, all(tracing_unstable, feature = "valuable")))]
+    pub fn with_duration_format(self, duration_format: crate::DurationFormat) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.duration_format = duration_format;
+            event_formatter
+        })
+    }
+
+    /// Configures the representation of `std::error::Error` fields and their sources (a structured
+    /// object with `message` and `causes` fields by default)
+    pub fn with_error_format(self, error_format: ErrorFormat) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.error_format = error_format;
+            event_formatter
+        })
+    }
+
+    /// Configures the minimum LogSeverity of Events that include a `stack_trace` field with a
+    /// backtrace of the Event's callsite (which Error Reporting uses to group errors), or disables
+    /// stack traces altogether with `None` (the default). Since capturing a backtrace is
+    /// expensive, stack traces should be limited to rare, high-severity Events. Backtraces that
+    /// can't be captured on the current platform are omitted.
+    pub fn with_stack_traces(self, threshold: Option<LogSeverity>) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.stack_trace_threshold = threshold;
+            event_formatter
+        })
+    }
+
+    /// Configures whether or not the `message` of error events with both a `std::error::Error`
+    /// field and a stack trace is replaced with the error followed by the stack trace on the
+    /// next lines, which Error Reporting groups errors by. The structured error and `stack_trace`
+    /// fields are kept as-is, so that both representations show the same text.
+    pub fn with_combined_error_messages(self, combine_error_messages: bool) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.combine_error_messages = combine_error_messages;
+            event_formatter
+        })
+    }
+
+    /// Configures the maximum number of sources (10 by default) that are included in the `causes`
+    /// of `std::error::Error` fields, protecting against very deep or cyclic source chains
+    pub fn with_error_chain_depth(self, error_chain_depth: usize) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.error_chain_depth = error_chain_depth;
+            event_formatter
+        })
+    }
+
+    /// Configures the representation of Event timestamps (an RFC3339-formatted `time` field by
+    /// default)
+    pub fn with_timestamp_style(self, timestamp_style: TimestampStyle) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.timestamp_style = timestamp_style;
+            event_formatter
+        })
+    }
+
+    /// Configures the key of the Event timestamp field (`time` for `TimestampStyle::Rfc3339`
+    /// timestamps and `timestamp` for `TimestampStyle::Structured` timestamps by default). The
+    /// keys of `TimestampStyle::SecondsNanos` timestamps are fixed.
+    pub fn with_time_key(self, time_key: impl Into<String>) -> Self {
+        let time_key = time_key.into();
+
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.time_key = Some(time_key);
+            event_formatter
+        })
+    }
+
+    /// Configures a custom textual representation of `TimestampStyle::Rfc3339` Event timestamps
+    /// (which are RFC3339-formatted with nanosecond precision by default) by writing each Event's
+    /// timestamp to the provided `String`. Formatted timestamps are escaped like any other string
+    /// field, so formatters can't produce invalid JSON.
+    pub fn with_time_formatter<F>(self, time_formatter: F) -> Self
+    where
+        F: Fn(OffsetDateTime, &mut String) + Send + Sync + 'static,
+    {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.time_formatter = Some(Box::new(time_formatter));
+            event_formatter
+        })
     }
 
-    /// Configures the Cloud Trace integration with OpenTelemetry through special LogEntry fields
+    /// Configures the Clock used for Event timestamps (the system clock by default)
+    pub fn with_clock<C>(self, clock: C) -> Self
+    where
+        C: Clock + 'static,
+    {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.clock = Box::new(clock);
+            event_formatter
+        })
+    }
+
+    /// Configures whether or not Events will be written as pretty-printed, multi-line JSON (e.g.
+    /// for local development) instead of the default compact, single-line JSON
+    pub fn with_pretty(self, pretty: bool) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.pretty = pretty;
+            event_formatter
+        })
+    }
+
+    /// Configures the key of the field that each Event's formatted message is written to
+    /// (`message` by default). Event fields with the same (camelCased) key take precedence, in
+    /// which case the message is written to the default `message` key instead, as are messages
+    /// whose key is written by the layer itself (e.g. `severity`, with a warning passed to the
+    /// Layer's `on_error` handler).
+    pub fn with_message_field(self, message_field: impl Into<String>) -> Self {
+        let message_field = message_field.into();
+
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.message_field = message_field;
+            event_formatter
+        })
+    }
+
+    /// Configures whether or not empty messages (e.g. `tracing::info!(count = 1, "")`) are
+    /// written to the message key (the default), or omitted along with the key itself. Events
+    /// without any message never include the key.
+    pub fn with_empty_messages(self, include_empty_messages: bool) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.include_empty_messages = include_empty_messages;
+            event_formatter
+        })
+    }
+
+    /// Configures whether or not `http_request.latency` fields are checked against the
+    /// `Duration` format expected by Cloud Logging (e.g. `0.23s`), passing a warning about any
+    /// values that can't be parsed (which Cloud Logging otherwise drops silently) to the Layer's
+    /// `on_error` handler
+    pub fn with_latency_validation(self, validate_latency: bool) -> Self {
+        self.map_event_format(|mut event_formatter| {
+            event_formatter.validate_latency = validate_latency;
+            event_formatter
+        })
+    }
+
+    /// Configures which span lifecycle Events (none by default) will be logged as `DEBUG`-level
+    /// "span opened" (when a span is first entered) and "span closed" Events of their own, e.g.
+    /// `SpanEvents::OPEN | SpanEvents::CLOSE`. Like any other Event within a span, these include
+    /// the span's name and fields.
+    pub fn with_span_events(mut self, events: SpanEvents) -> Self {
+        self.1.events = events;
+        self
+    }
+
+    /// Configures whether or not a `DEBUG`-level "span closed" Event will be emitted when each span
+    /// closes, including the span's total `elapsed_ms` along with the `busy_ns` spent inside of
+    /// the span and the `idle_ns` spent outside of it
+    pub fn with_span_timing(mut self, timing: bool) -> Self {
+        self.1.timing = timing;
+        self
+    }
+
+    /// Configures the Cloud Trace integration with OpenTelemetry through special LogEntry fields,
+    /// which is equivalent to [`with_project_id`](Self::with_project_id) since the trace and
+    /// span IDs of OpenTelemetry spans are included whenever the `opentelemetry` feature is
+    /// enabled
     #[cfg_attr(docsrs, doc(cfg(feature = "opentelemetry")))]
     #[cfg(any(docsrs, feature = "opentelemetry"))]
     pub fn with_cloud_trace(self, configuration: crate::CloudTraceConfiguration) -> Self {
-        Self(self.0.map_event_format(|mut event_formatter| {
-            event_formatter.cloud_trace_configuration = Some(configuration);
-            event_formatter
-        }))
+        self.with_project_id(configuration.project_id)
+    }
+}
+
+impl<S, W> Layer<S, W>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    W: for<'writer> MakeWriter<'writer> + 'static,
+{
+    /// Maps the EventFormatter of the Layer being built, retaining the rest of its configuration
+    fn map_event_format(self, mapper: impl FnOnce(EventFormatter) -> EventFormatter) -> Self {
+        Self(self.0.map_event_format(mapper), self.1)
+    }
+}
+
+impl<W> Layer<Registry, W>
+where
+    W: for<'writer> MakeWriter<'writer> + 'static,
+{
+    /// Runs a callback with this Layer's configuration (and a default `Registry`) as the default
+    /// subscriber, returning the Events emitted by the callback as JSON values rather than
+    /// writing them. Events are serialized exactly as they would be written, making this useful
+    /// for asserting on the structure of log entries in tests.
+    pub fn as_json(self, callback: impl FnOnce()) -> serde_json::Result<Vec<serde_json::Value>> {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let shared = buffer.clone();
+        let make_writer = move || BufferWriter(shared.clone());
+        let subscriber = Registry::default().with(self.with_writer(make_writer));
+
+        tracing::subscriber::with_default(subscriber, callback);
+
+        let buffer = buffer.lock().unwrap_or_else(|error| error.into_inner());
+
+        serde_json::Deserializer::from_slice(&buffer)
+            .into_iter()
+            .collect()
     }
 }
 
@@ -87,7 +777,17 @@ where
         id: &tracing_core::span::Id,
         context: tracing_subscriber::layer::Context<'_, S>,
     ) {
-        self.0.on_new_span(attrs, id, context)
+        if self.1.timing {
+            if let Some(span) = context.span(id) {
+                span.extensions_mut().insert(Timings::new());
+            }
+        }
+
+        self.0.on_new_span(attrs, id, context.clone());
+
+        if let Some(span) = context.span(id) {
+            cache_span_fields(&span);
+        }
     }
 
     fn on_record(
@@ -96,7 +796,11 @@ where
         values: &tracing_core::span::Record<'_>,
         context: tracing_subscriber::layer::Context<'_, S>,
     ) {
-        self.0.on_record(span, values, context)
+        self.0.on_record(span, values, context.clone());
+
+        if let Some(span) = context.span(span) {
+            cache_span_fields(&span);
+        }
     }
 
     fn on_enter(
@@ -104,6 +808,24 @@ where
         id: &tracing_core::span::Id,
         context: tracing_subscriber::layer::Context<'_, S>,
     ) {
+        if let Some(span) = context.span(id) {
+            let mut extensions = span.extensions_mut();
+
+            if let Some(timings) = extensions.get_mut::<Timings>() {
+                timings.enter();
+            }
+
+            let is_opened =
+                self.1.events.contains(SpanEvents::OPEN) && extensions.replace(Opened).is_none();
+            drop(extensions);
+
+            if is_opened {
+                with_span_event(id, "span opened", None, |event| {
+                    self.0.on_event(event, context.clone())
+                });
+            }
+        }
+
         self.0.on_enter(id, context)
     }
 
@@ -112,6 +834,12 @@ where
         id: &tracing_core::span::Id,
         context: tracing_subscriber::layer::Context<'_, S>,
     ) {
+        if let Some(span) = context.span(id) {
+            if let Some(timings) = span.extensions_mut().get_mut::<Timings>() {
+                timings.exit();
+            }
+        }
+
         self.0.on_exit(id, context)
     }
 
@@ -120,6 +848,19 @@ where
         id: tracing_core::span::Id,
         context: tracing_subscriber::layer::Context<'_, S>,
     ) {
+        if self.1.timing || self.1.events.contains(SpanEvents::CLOSE) {
+            let timings = context.span(&id).and_then(|span| {
+                let mut extensions = span.extensions_mut();
+                let timings = extensions.get_mut::<Timings>()?;
+                timings.close();
+                Some(timings.clone())
+            });
+
+            with_span_event(&id, "span closed", timings.as_ref(), |event| {
+                self.0.on_event(event, context.clone())
+            });
+        }
+
         self.0.on_close(id, context)
     }
 
@@ -128,15 +869,32 @@ where
     }
 
     unsafe fn downcast_raw(&self, id: std::any::TypeId) -> Option<*const ()> {
+        // expose a hook for attaching trace contexts to this Layer's spans
+        if id == std::any::TypeId::of::<WithContext>() {
+            let with_context: &'static WithContext = &Hook::<S>::WITH_CONTEXT;
+            return Some(with_context as *const WithContext as *const ());
+        }
+
         self.0.downcast_raw(id)
     }
 }
 
+/// Subscriber-specific hooks, which are promoted to statics for use in `downcast_raw`
+struct Hook<S>(std::marker::PhantomData<S>);
+
+impl<S> Hook<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    const WITH_CONTEXT: WithContext = WithContext::new::<S>();
+}
+
 impl<S, W> Deref for Layer<S, W>
 where
     S: Subscriber + for<'span> LookupSpan<'span>,
 {
-    type Target = tracing_subscriber::fmt::Layer<S, JsonFields, EventFormatter, W>;
+    type Target =
+        tracing_subscriber::fmt::Layer<S, JsonFields, EventFormatter, WithErrorHandler<W>>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
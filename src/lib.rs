@@ -4,12 +4,55 @@
 #![allow(clippy::needless_doctest_main)]
 #![doc = include_str!("../README.md")]
 
+mod builder;
+mod clock;
+#[cfg(any(docsrs, all(tracing_unstable, feature = "valuable")))]
+mod duration_value;
+mod environment;
+mod error_value;
 mod event_formatter;
+mod field_filter;
 mod google;
+mod insert_id;
+#[cfg(any(docsrs, all(tracing_unstable, feature = "valuable")))]
+mod json_value;
 mod layer;
+#[cfg(feature = "tower")]
+mod middleware;
+mod panic;
 mod serializers;
+mod span_events;
+mod trace_context;
 mod visitor;
 mod writer;
 
+pub use self::builder::StackdriverBuilder;
+pub use self::clock::*;
+#[cfg(any(docsrs, all(tracing_unstable, feature = "valuable")))]
+pub use self::duration_value::DurationValue;
+#[cfg(any(docsrs, feature = "resource-detection"))]
+pub use self::environment::{detect_resource, DetectedResource};
+#[cfg(any(docsrs, feature = "anyhow"))]
+pub use self::error_value::error_value;
+#[cfg(any(docsrs, feature = "eyre"))]
+pub use self::error_value::report_value;
+pub use self::error_value::ErrorValue;
 pub use self::google::*;
+#[cfg(any(docsrs, all(tracing_unstable, feature = "valuable")))]
+pub use self::json_value::JsonValue;
 pub use self::layer::*;
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+#[cfg(feature = "tower")]
+pub use self::middleware::{HttpLayer, ResponseFuture, TraceContextLayer, TraceContextService};
+pub use self::panic::{install_panic_hook, PanicHook};
+pub use self::span_events::SpanEvents;
+pub use self::trace_context::*;
+pub use self::writer::{ErrorHandlingWriter, WithErrorHandler};
+
+/// Items used by exported macros, which aren't part of the public API
+#[doc(hidden)]
+#[cfg(feature = "http-request")]
+pub mod __private {
+    pub use crate::google::HttpRequestFields;
+    pub use tracing;
+}
@@ -0,0 +1,134 @@
+use crate::{google::HttpRequest, trace_context::TraceContext};
+use pin_project_lite::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+    time::Instant,
+};
+use tower::{Layer, Service};
+use tracing::{field::Empty, Level, Span};
+
+/// [`tower`](https://docs.rs/tower) middleware that wraps each request in a `request` span
+/// correlated with the trace context of its `traceparent` or `X-Cloud-Trace-Context` header (in
+/// that order of precedence), and emits an event with the complete `httpRequest` of the request
+/// and its response (including their sizes and the latency of the response) once the response
+/// is ready. Requests that fail in the inner service emit an `ERROR`-level event with the
+/// request-side fields and latency instead.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TraceContextLayer;
+
+/// Alias of [`TraceContextLayer`], the middleware that records the `httpRequest` of each request
+pub type HttpLayer = TraceContextLayer;
+
+impl TraceContextLayer {
+    /// Create a new trace context middleware layer
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<S> Layer<S> for TraceContextLayer {
+    type Service = TraceContextService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TraceContextService { inner }
+    }
+}
+
+/// [`tower`](https://docs.rs/tower) service created by [`TraceContextLayer`]
+#[derive(Clone, Debug)]
+pub struct TraceContextService<S> {
+    inner: S,
+}
+
+impl<S, RequestBody, ResponseBody> Service<http::Request<RequestBody>> for TraceContextService<S>
+where
+    S: Service<http::Request<RequestBody>, Response = http::Response<ResponseBody>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, context: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(context)
+    }
+
+    fn call(&mut self, request: http::Request<RequestBody>) -> Self::Future {
+        // latencies include any synchronous work of the inner service's `call`
+        let start = Instant::now();
+        let span = tracing::info_span!(
+            "request",
+            trace_id = Empty,
+            span_id = Empty,
+            trace_sampled = Empty
+        );
+
+        if let Some(trace_context) = trace_context(request.headers()) {
+            trace_context.record(&span);
+        }
+
+        let http_request = HttpRequest::from(&request);
+        let inner = span.in_scope(|| self.inner.call(request));
+
+        ResponseFuture {
+            inner,
+            span,
+            http_request: Some(http_request),
+            start,
+        }
+    }
+}
+
+pin_project! {
+    /// Response future of [`TraceContextService`]
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: F,
+        span: Span,
+        http_request: Option<HttpRequest>,
+        start: Instant,
+    }
+}
+
+impl<F, ResponseBody, Error> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<http::Response<ResponseBody>, Error>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let _span = this.span.enter();
+        let result = ready!(this.inner.poll(context));
+        let latency = this.start.elapsed();
+
+        // ready futures are never polled again, so the HttpRequest is moved out of the future
+        if let Some(mut http_request) = this.http_request.take() {
+            match &result {
+                Ok(response) => {
+                    http_request.merge_response(response, latency);
+                    crate::http_request_event!(Level::INFO, http_request, "request completed");
+                }
+                Err(_) => {
+                    http_request.latency = Some(latency);
+                    crate::http_request_event!(Level::ERROR, http_request, "request failed");
+                }
+            }
+        }
+
+        Poll::Ready(result)
+    }
+}
+
+/// Trace context of the first valid trace context header of a request
+fn trace_context(headers: &http::HeaderMap) -> Option<TraceContext> {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+
+    header("traceparent")
+        .and_then(|header| TraceContext::from_traceparent(header).ok())
+        .or_else(|| {
+            header("x-cloud-trace-context")
+                .and_then(|header| TraceContext::from_cloud_trace_header(header).ok())
+        })
+}
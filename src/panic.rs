@@ -0,0 +1,105 @@
+use crate::google::{LogSeverity, ServiceContext};
+use std::{any::Any, backtrace::Backtrace, cell::Cell, panic};
+
+thread_local! {
+    /// Whether or not the current thread is already reporting a panic, which guards against
+    /// panics raised while the panic is being logged
+    static REPORTING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Install a panic hook that emits an `ERROR`-level Event for every panic through the current
+/// subscriber, with the panic's payload followed by a backtrace as its message (which Error
+/// Reporting parses to group errors), its location in a `panic_location` field, and the
+/// backtrace in a `stack_trace` field as well.
+/// The previous panic hook is called afterwards, so its behavior (e.g. printing the panic to
+/// `stderr`) is preserved. Panics raised while a panic is being logged skip straight to the
+/// previous hook. Use [`PanicHook`] to configure the severity or service context of these Events.
+pub fn install_panic_hook() {
+    PanicHook::default().install();
+}
+
+/// Builder for the panic hook installed by [`install_panic_hook`]
+#[derive(Clone, Debug)]
+pub struct PanicHook {
+    service_context: Option<ServiceContext>,
+    severity: LogSeverity,
+}
+
+impl Default for PanicHook {
+    fn default() -> Self {
+        Self {
+            service_context: None,
+            severity: LogSeverity::Error,
+        }
+    }
+}
+
+impl PanicHook {
+    /// Create a panic hook with the default (`ERROR`-level) configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report panics to Error Reporting with the given service context, in place of the service
+    /// context of the layer (when configured with `with_error_reporting`)
+    pub fn with_service_context(self, service_context: ServiceContext) -> Self {
+        Self {
+            service_context: Some(service_context),
+            ..self
+        }
+    }
+
+    /// Set the severity of panic Events (default: `ERROR`), e.g. `LogSeverity::Critical` for
+    /// panics that bring down the whole service
+    pub fn with_severity(self, severity: LogSeverity) -> Self {
+        Self { severity, ..self }
+    }
+
+    /// Install the panic hook, chaining to the previous panic hook
+    pub fn install(self) {
+        let previous_hook = panic::take_hook();
+
+        panic::set_hook(Box::new(move |info| {
+            // thread-locals are unavailable while the thread is being torn down
+            let reporting = REPORTING.try_with(|reporting| reporting.replace(true));
+
+            if let Ok(false) = reporting {
+                let payload = payload(info.payload());
+                let location = info
+                    .location()
+                    .map(|location| location.to_string())
+                    .unwrap_or_default();
+                let backtrace = Backtrace::force_capture().to_string();
+                let service_context = self.service_context.as_ref();
+                let service = service_context.map(|context| context.service.as_str());
+                let version = service_context.and_then(|context| context.version.as_deref());
+
+                tracing::error!(
+                    severity = %self.severity,
+                    service_context.service = service,
+                    service_context.version = version,
+                    panic_location = location.as_str(),
+                    stack_trace = backtrace.as_str(),
+                    "panicked: {}\n{}",
+                    payload,
+                    backtrace
+                );
+
+                let _ = REPORTING.try_with(|reporting| reporting.set(false));
+            }
+
+            previous_hook(info);
+        }));
+    }
+}
+
+/// Message of a panic payload, which is a string for panics raised through `panic!`
+fn payload(payload: &(dyn Any + Send)) -> &str {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message,
+        None => payload
+            .downcast_ref::<String>()
+            .map(String::as_str)
+            .unwrap_or("Box<dyn Any>"),
+    }
+}
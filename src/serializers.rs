@@ -1,13 +1,15 @@
+use crate::field_filter::FieldFilter;
+use inflector::Inflector;
 use serde::ser::{Serialize, SerializeMap, SerializeSeq};
-use serde_json::Value;
-use tracing_core::Subscriber;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
 use tracing_subscriber::{
-    fmt::{format::JsonFields, FmtContext, FormattedFields},
+    fmt::{format::JsonFields, FormattedFields},
     registry::{LookupSpan, SpanRef},
 };
 
 /// Serializable tracing span for nesting formatted event fields
-pub(crate) struct SerializableSpan<'a, 'b, S>(&'b SpanRef<'a, S>)
+pub(crate) struct SerializableSpan<'a, 'b, S>(&'b SpanRef<'a, S>, &'b FieldFilter, &'b str)
 where
     S: for<'lookup> LookupSpan<'lookup>;
 
@@ -15,8 +17,12 @@ impl<'a, 'b, S> SerializableSpan<'a, 'b, S>
 where
     S: for<'lookup> LookupSpan<'lookup>,
 {
-    pub(crate) fn new(span: &'b SpanRef<'a, S>) -> Self {
-        Self(span)
+    pub(crate) fn new(
+        span: &'b SpanRef<'a, S>,
+        field_filter: &'b FieldFilter,
+        name_key: &'b str,
+    ) -> Self {
+        Self(span, field_filter, name_key)
     }
 }
 
@@ -29,50 +35,109 @@ where
         R: serde::Serializer,
     {
         let name = self.0.name();
-        let extensions = self.0.extensions();
-
-        let formatted_fields = extensions
-            .get::<FormattedFields<JsonFields>>()
-            .expect("No fields!");
-
-        let span_length = formatted_fields.fields.len() + 1;
-        let mut map = serializer.serialize_map(Some(span_length))?;
-
-        match serde_json::from_str::<Value>(formatted_fields) {
-            // handle string escaping "properly" (this should be fixed upstream)
-            // https://github.com/tokio-rs/tracing/issues/391
-            Ok(Value::Object(fields)) => {
-                for (key, value) in fields {
-                    map.serialize_entry(&key, &value)?;
-                }
+        let mut fields = span_fields(self.0);
+        fields.retain(|key, _| self.1.allows(key));
+
+        for (key, value) in fields.iter_mut() {
+            self.1.redact(key, value);
+        }
+
+        // fields that share a key with the span's name are kept alongside it, under a key that's
+        // renamed like those of event fields
+        if let Some(value) = fields.remove(self.2) {
+            let mut key = format!("{}_value", self.2).to_camel_case();
+
+            while fields.contains_key(&key) {
+                key.push_str("Value");
             }
-            // these two options should be impossible
-            Ok(value) => panic!("Invalid value: {}", value),
-            Err(error) => panic!("Error parsing logs: {}", error),
-        };
 
-        map.serialize_entry("name", &name)?;
+            fields.insert(key, value);
+        }
+
+        let mut map = serializer.serialize_map(Some(fields.len() + 1))?;
+
+        for (key, value) in fields {
+            map.serialize_entry(&key, &value)?;
+        }
+
+        map.serialize_entry(self.2, &name)?;
         map.end()
     }
 }
 
-/// Serializable tracing context for serializing a collection of spans
-pub(crate) struct SerializableContext<'a, 'b, S>(&'b FmtContext<'a, S, JsonFields>)
+/// Fields recorded on a span, which are parsed once per recording and cached in the span's
+/// extensions rather than parsed again for each of its events
+pub(crate) struct SpanFields(Map<String, Value>);
+
+impl SpanFields {
+    /// Returns the value of a cached field
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        self.0.get(key)
+    }
+}
+
+/// Parse the JSON-formatted fields recorded on a span into its cached fields
+pub(crate) fn cache_span_fields<S>(span: &SpanRef<'_, S>)
 where
-    S: Subscriber + for<'lookup> LookupSpan<'lookup>;
+    S: for<'lookup> LookupSpan<'lookup>,
+{
+    let fields = parse_span_fields(span);
+    span.extensions_mut().replace(SpanFields(fields));
+}
+
+/// Returns the fields recorded on a span, parsing them if they haven't been cached
+pub(crate) fn span_fields<S>(span: &SpanRef<'_, S>) -> Map<String, Value>
+where
+    S: for<'lookup> LookupSpan<'lookup>,
+{
+    match span.extensions().get::<SpanFields>() {
+        Some(SpanFields(fields)) => fields.clone(),
+        None => parse_span_fields(span),
+    }
+}
+
+/// Parse the JSON-formatted fields recorded on a span
+fn parse_span_fields<S>(span: &SpanRef<'_, S>) -> Map<String, Value>
+where
+    S: for<'lookup> LookupSpan<'lookup>,
+{
+    let extensions = span.extensions();
+
+    let formatted_fields = extensions
+        .get::<FormattedFields<JsonFields>>()
+        .expect("No fields!");
+
+    match serde_json::from_str::<Value>(formatted_fields) {
+        // handle string escaping "properly" (this should be fixed upstream)
+        // https://github.com/tokio-rs/tracing/issues/391
+        Ok(Value::Object(fields)) => fields,
+        // these two options should be impossible
+        Ok(value) => panic!("Invalid value: {}", value),
+        Err(error) => panic!("Error parsing logs: {}", error),
+    }
+}
+
+/// Serializable tracing context for serializing a span and its ancestors, from the root span down
+pub(crate) struct SerializableContext<'a, 'b, S>(&'b SpanRef<'a, S>, &'b FieldFilter, &'b str)
+where
+    S: for<'lookup> LookupSpan<'lookup>;
 
 impl<'a, 'b, S> SerializableContext<'a, 'b, S>
 where
-    S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+    S: for<'lookup> LookupSpan<'lookup>,
 {
-    pub(crate) fn new(context: &'b FmtContext<'a, S, JsonFields>) -> Self {
-        Self(context)
+    pub(crate) fn new(
+        leaf_span: &'b SpanRef<'a, S>,
+        field_filter: &'b FieldFilter,
+        name_key: &'b str,
+    ) -> Self {
+        Self(leaf_span, field_filter, name_key)
     }
 }
 
 impl<'a, 'b, S> Serialize for SerializableContext<'a, 'b, S>
 where
-    S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+    S: for<'lookup> LookupSpan<'lookup>,
 {
     fn serialize<R>(&self, serializer: R) -> Result<R::Ok, R::Error>
     where
@@ -80,10 +145,8 @@ where
     {
         let mut list = serializer.serialize_seq(None)?;
 
-        if let Some(leaf_span) = self.0.lookup_current() {
-            for span in leaf_span.scope().from_root() {
-                list.serialize_element(&SerializableSpan::new(&span))?;
-            }
+        for span in self.0.scope().from_root() {
+            list.serialize_element(&SerializableSpan::new(&span, self.1, self.2))?;
         }
 
         list.end()
@@ -93,6 +156,7 @@ where
 pub(crate) struct SourceLocation<'a> {
     pub(crate) file: &'a str,
     pub(crate) line: Option<u32>,
+    pub(crate) function: Option<&'a str>,
 }
 
 impl<'a> Serialize for SourceLocation<'a> {
@@ -100,13 +164,70 @@ impl<'a> Serialize for SourceLocation<'a> {
     where
         R: serde::Serializer,
     {
-        let mut map = serializer.serialize_map(Some(if self.line.is_some() { 2 } else { 1 }))?;
+        let length = 1 + self.line.is_some() as usize + self.function.is_some() as usize;
+        let mut map = serializer.serialize_map(Some(length))?;
         map.serialize_entry("file", self.file)?;
         if let Some(line) = self.line {
             // Stackdriver expects the line number to be serialised as a string:
             // https://cloud.google.com/logging/docs/reference/v2/rest/v2/LogEntry#LogEntrySourceLocation
             map.serialize_entry("line", &line.to_string())?;
         }
+        if let Some(function) = self.function {
+            // tracing doesn't track function names, so the module path is the closest equivalent
+            map.serialize_entry("function", function)?;
+        }
         map.end()
     }
 }
+
+/// Structured timestamp, as [recognized by the logging
+/// agent](https://cloud.google.com/logging/docs/agent/logging/configuration#timestamp-processing)
+#[derive(serde::Serialize)]
+pub(crate) struct Timestamp {
+    pub(crate) seconds: i64,
+    pub(crate) nanos: u32,
+}
+
+/// Thread that emitted an Event
+#[derive(serde::Serialize)]
+pub(crate) struct Thread<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) id: Option<String>,
+}
+
+/// Process that emitted an Event
+#[derive(serde::Serialize)]
+pub(crate) struct Process {
+    pub(crate) pid: u32,
+}
+
+/// Host of the process that emitted an Event
+#[derive(serde::Serialize)]
+pub(crate) struct Host<'a> {
+    pub(crate) name: &'a str,
+}
+
+/// Error Reporting context of an error Event
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ErrorContext<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) report_location: Option<ReportLocation<'a>>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) http_request: BTreeMap<&'static str, Value>,
+}
+
+/// Location in the source code where an error was reported, as [recognized by Error
+/// Reporting](https://cloud.google.com/error-reporting/reference/rest/v1beta1/ErrorContext#SourceLocation)
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ReportLocation<'a> {
+    pub(crate) file_path: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) line_number: Option<u32>,
+    // tracing doesn't track function names, so the module path is the closest equivalent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) function_name: Option<&'a str>,
+}
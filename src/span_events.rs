@@ -0,0 +1,165 @@
+use std::{
+    ops,
+    time::{Duration, Instant},
+};
+use tracing_core::{
+    callsite::{Callsite, Identifier},
+    field::{FieldSet, Value},
+    metadata::Kind,
+    span::Id,
+    subscriber::Interest,
+    Event, Level, Metadata,
+};
+
+/// Lifecycle Events of spans that are logged as `DEBUG`-level Events of their own, which can be
+/// combined with `|`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SpanEvents(u8);
+
+impl SpanEvents {
+    /// No span lifecycle Events
+    pub const NONE: Self = Self(0);
+    /// A "span opened" Event when a span is first entered
+    pub const OPEN: Self = Self(1 << 0);
+    /// A "span closed" Event when a span is closed
+    pub const CLOSE: Self = Self(1 << 1);
+
+    /// Whether or not all of the provided span lifecycle Events are included
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl ops::BitOr for SpanEvents {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+/// Configuration of the Events that a Layer emits for the lifecycle of spans
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct SpanConfiguration {
+    pub(crate) events: SpanEvents,
+    pub(crate) timing: bool,
+}
+
+/// Marker for spans that have been entered, stored in the span's extensions so that re-entered
+/// spans (e.g. those of futures) are only logged as opened once
+pub(crate) struct Opened;
+
+/// Time spent inside (busy) and outside (idle) of a span since its creation, stored in the span's
+/// extensions
+#[derive(Clone)]
+pub(crate) struct Timings {
+    created: Instant,
+    last: Instant,
+    busy: Duration,
+    idle: Duration,
+}
+
+impl Timings {
+    pub(crate) fn new() -> Self {
+        let now = Instant::now();
+
+        Self {
+            created: now,
+            last: now,
+            busy: Duration::ZERO,
+            idle: Duration::ZERO,
+        }
+    }
+
+    /// Record the end of an idle period when the span is entered
+    pub(crate) fn enter(&mut self) {
+        let now = Instant::now();
+        self.idle += now - self.last;
+        self.last = now;
+    }
+
+    /// Record the end of a busy period when the span is exited
+    pub(crate) fn exit(&mut self) {
+        let now = Instant::now();
+        self.busy += now - self.last;
+        self.last = now;
+    }
+
+    /// Record the end of the final idle period when the span is closed
+    pub(crate) fn close(&mut self) {
+        self.enter();
+    }
+}
+
+/// Callsite of the Events emitted for the lifecycle of spans
+struct SpanEventCallsite;
+
+static SPAN_EVENT_CALLSITE: SpanEventCallsite = SpanEventCallsite;
+
+static SPAN_EVENT_METADATA: Metadata<'static> = Metadata::new(
+    "span",
+    "tracing_stackdriver",
+    Level::DEBUG,
+    None,
+    None,
+    None,
+    FieldSet::new(
+        &["message", "elapsed_ms", "busy_ns", "idle_ns"],
+        Identifier(&SPAN_EVENT_CALLSITE),
+    ),
+    Kind::EVENT,
+);
+
+impl Callsite for SpanEventCallsite {
+    fn set_interest(&self, _: Interest) {}
+
+    fn metadata(&self) -> &Metadata<'_> {
+        &SPAN_EVENT_METADATA
+    }
+}
+
+/// Build a `DEBUG`-level Event within a span, including the span's timings (if any), and pass it
+/// to the provided callback
+pub(crate) fn with_span_event(
+    id: &Id,
+    message: &str,
+    timings: Option<&Timings>,
+    callback: impl FnOnce(&Event<'_>),
+) {
+    let fields = SPAN_EVENT_METADATA.fields();
+    let field = |name| {
+        fields
+            .field(name)
+            .expect("span event fields should be declared")
+    };
+
+    let (message_field, elapsed_field, busy_field, idle_field) = (
+        field("message"),
+        field("elapsed_ms"),
+        field("busy_ns"),
+        field("idle_ns"),
+    );
+
+    let message = format_args!("{message}");
+    let elapsed = timings.map(|timings| timings.created.elapsed().as_millis() as u64);
+    let busy = timings.map(|timings| timings.busy.as_nanos() as u64);
+    let idle = timings.map(|timings| timings.idle.as_nanos() as u64);
+
+    let values = [
+        (&message_field, Some(&message as &dyn Value)),
+        (
+            &elapsed_field,
+            elapsed.as_ref().map(|value| value as &dyn Value),
+        ),
+        (&busy_field, busy.as_ref().map(|value| value as &dyn Value)),
+        (&idle_field, idle.as_ref().map(|value| value as &dyn Value)),
+    ];
+
+    let values = fields.value_set(&values);
+
+    callback(&Event::new_child_of(
+        id.clone(),
+        &SPAN_EVENT_METADATA,
+        &values,
+    ));
+}
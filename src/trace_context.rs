@@ -0,0 +1,192 @@
+use tracing::Span;
+use tracing_core::{span::Id, Dispatch, Subscriber};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Errors encountered while parsing trace context headers
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+    /// The header version is malformed or unsupported
+    #[error("invalid version")]
+    InvalidVersion,
+    /// The trace ID is missing, is not a 32-character hex string, or is invalid for the header
+    /// format
+    #[error("invalid trace ID")]
+    InvalidTraceId,
+    /// The span ID is not a valid span ID for the header format
+    #[error("invalid span ID")]
+    InvalidSpanId,
+    /// The trace options (e.g. the `;o=1` suffix or `traceparent` flags) are malformed
+    #[error("invalid trace options")]
+    InvalidOptions,
+}
+
+/// Trace context propagated to a service by an incoming request, e.g. through the
+/// [`X-Cloud-Trace-Context`](https://cloud.google.com/trace/docs/trace-context#legacy-http-header)
+/// header added by Google Cloud load balancers or the W3C
+/// [`traceparent`](https://www.w3.org/TR/trace-context/#traceparent-header) header
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceContext {
+    /// 32-character hex trace ID
+    pub trace_id: String,
+    /// ID of the caller's span, if any
+    pub span_id: Option<u64>,
+    /// Whether or not the trace has been sampled
+    pub sampled: bool,
+}
+
+impl TraceContext {
+    /// Parse the value of an `X-Cloud-Trace-Context` header, formatted as
+    /// `TRACE_ID/SPAN_ID;o=OPTIONS` (where both the span ID and options are optional, and the span
+    /// ID is a decimal number)
+    pub fn from_cloud_trace_header(header: &str) -> Result<Self, ParseError> {
+        let (context, options) = match header.trim().split_once(';') {
+            Some((context, options)) => (context, Some(options)),
+            None => (header.trim(), None),
+        };
+
+        let (trace_id, span_id) = match context.split_once('/') {
+            Some((trace_id, span_id)) => (trace_id, Some(span_id)),
+            None => (context, None),
+        };
+
+        if !is_hex(trace_id, 32) {
+            return Err(ParseError::InvalidTraceId);
+        }
+
+        let span_id = match span_id {
+            Some("") | None => None,
+            Some(span_id) => Some(span_id.parse().map_err(|_| ParseError::InvalidSpanId)?),
+        };
+
+        let sampled = match options {
+            None | Some("o=0") => false,
+            Some("o=1") => true,
+            Some(_) => return Err(ParseError::InvalidOptions),
+        };
+
+        Ok(Self {
+            trace_id: trace_id.to_ascii_lowercase(),
+            span_id,
+            sampled,
+        })
+    }
+
+    /// Parse the value of a W3C `traceparent` header, formatted as
+    /// `VERSION-TRACE_ID-PARENT_ID-FLAGS` (where the parent ID is a 16-character hex string)
+    pub fn from_traceparent(header: &str) -> Result<Self, ParseError> {
+        let mut parts = header.trim().split('-');
+
+        let version = parts.next().ok_or(ParseError::InvalidVersion)?;
+
+        // version "ff" is forbidden, and newer versions may only append fields
+        if !is_hex(version, 2) || version.eq_ignore_ascii_case("ff") {
+            return Err(ParseError::InvalidVersion);
+        }
+
+        let trace_id = parts.next().ok_or(ParseError::InvalidTraceId)?;
+
+        if !is_hex(trace_id, 32) || is_zero(trace_id) {
+            return Err(ParseError::InvalidTraceId);
+        }
+
+        let span_id = parts.next().ok_or(ParseError::InvalidSpanId)?;
+
+        if !is_hex(span_id, 16) || is_zero(span_id) {
+            return Err(ParseError::InvalidSpanId);
+        }
+
+        let flags = parts
+            .next()
+            .filter(|flags| is_hex(flags, 2))
+            .and_then(|flags| u8::from_str_radix(flags, 16).ok())
+            .ok_or(ParseError::InvalidOptions)?;
+
+        if version == "00" && parts.next().is_some() {
+            return Err(ParseError::InvalidVersion);
+        }
+
+        Ok(Self {
+            trace_id: trace_id.to_ascii_lowercase(),
+            span_id: u64::from_str_radix(span_id, 16).ok(),
+            sampled: flags & 0x01 == 0x01,
+        })
+    }
+
+    /// Attach this trace context to a span, so that the span's events (and those of its
+    /// descendants) include it in their special Cloud Trace LogEntry fields without any fields
+    /// being declared or recorded on the span. Trace contexts can only be attached to spans of
+    /// subscribers that include a Stackdriver layer, and are ignored otherwise.
+    pub fn attach(&self, span: &Span) {
+        span.with_subscriber(|(id, dispatch)| {
+            if let Some(with_context) = dispatch.downcast_ref::<WithContext>() {
+                (with_context.0)(dispatch, id, self.clone());
+            }
+        });
+    }
+
+    /// Record this trace context onto the `trace_id`, `span_id`, and `trace_sampled` fields of a
+    /// span, which are then included in the special Cloud Trace LogEntry fields of the span's
+    /// events. Since `tracing` spans only record fields that were declared up front, these fields
+    /// should be declared as `tracing::field::Empty` when the span is created.
+    pub fn record(&self, span: &Span) {
+        span.record("trace_id", self.trace_id.as_str());
+
+        if let Some(span_id) = self.span_id {
+            span.record("span_id", format!("{:016x}", span_id).as_str());
+        }
+
+        span.record("trace_sampled", self.sampled);
+    }
+}
+
+/// Parse the value of an `X-Cloud-Trace-Context` header (as passed to services by Cloud Run and
+/// App Engine), returning `None` for malformed headers. See
+/// [`TraceContext::from_cloud_trace_header`] for details of the header format and parse errors.
+pub fn parse_cloud_trace_context(header: &str) -> Option<TraceContext> {
+    TraceContext::from_cloud_trace_header(header).ok()
+}
+
+/// Parse the value of a W3C `traceparent` header (as passed to services behind
+/// standards-compliant gateways), returning `None` for malformed headers. See
+/// [`TraceContext::from_traceparent`] for details of the header format and parse errors.
+pub fn parse_traceparent(header: &str) -> Option<TraceContext> {
+    TraceContext::from_traceparent(header).ok()
+}
+
+/// Type-erased hook for attaching trace contexts to the extensions of a subscriber's spans, which
+/// is exposed by the Stackdriver layer through `downcast_raw` (since neither the layer nor the
+/// type of its subscriber are known to the spans themselves)
+pub(crate) struct WithContext(fn(&Dispatch, &Id, TraceContext));
+
+impl WithContext {
+    /// Hook for the subscriber of the provided type
+    pub(crate) const fn new<S>() -> Self
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        Self(attach_to_extensions::<S>)
+    }
+}
+
+/// Attach a trace context to the extensions of a subscriber's span
+fn attach_to_extensions<S>(dispatch: &Dispatch, id: &Id, trace_context: TraceContext)
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    if let Some(span) = dispatch
+        .downcast_ref::<S>()
+        .and_then(|subscriber| subscriber.span(id))
+    {
+        span.extensions_mut().replace(trace_context);
+    }
+}
+
+/// Whether or not a header segment is a hex string of exactly the expected length
+fn is_hex(segment: &str, length: usize) -> bool {
+    segment.len() == length && segment.chars().all(|char| char.is_ascii_hexdigit())
+}
+
+/// Whether or not a hex header segment is all zeroes, which the W3C spec treats as invalid
+fn is_zero(segment: &str) -> bool {
+    segment.chars().all(|char| char == '0')
+}
@@ -1,32 +1,104 @@
-use crate::google::LogSeverity;
+use crate::{
+    error_value::ErrorValue,
+    event_formatter::EventFormatter,
+    google::{
+        format_duration, ControlCharHandling, ErrorFormat, LogSeverity, SeverityFormat,
+        REPORTED_ERROR_EVENT_TYPE,
+    },
+    serializers::{ErrorContext, ReportLocation},
+};
 use inflector::Inflector;
 use serde::ser::SerializeMap;
-use std::{collections::BTreeMap, fmt};
-use tracing_core::Field;
+use std::{
+    backtrace::{Backtrace, BacktraceStatus},
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    time::Duration,
+};
+use tracing_core::{Event, Field, Level, Metadata};
+use tracing_log::NormalizeEvent;
 use tracing_subscriber::field::{Visit, VisitOutput};
 
+/// Placeholder for field values that fail to serialize, which are replaced rather than losing the
+/// rest of the Event
+const UNSERIALIZABLE: &str = "<unserializable>";
+
+/// Fields that `tracing-log` records the metadata of forwarded `log` records in
+const LOG_FIELDS: [&str; 4] = ["log.target", "log.module_path", "log.file", "log.line"];
+
+/// Fields that are mapped to special LogEntry fields or identify the type of the Event, which are
+/// never truncated
+const SPECIAL_FIELDS: &[&str] = &[
+    "trace_id",
+    "span_id",
+    "trace_sampled",
+    "insert_id",
+    "@type",
+    "http_request",
+];
+/// Field prefixes that are mapped to special LogEntry fields, which are never truncated
+const SPECIAL_PREFIXES: &[&str] = &["http_request.", "labels.", "operation."];
+
 /// Visitor for Stackdriver events that formats custom fields
 pub(crate) struct Visitor<'a, S>
 where
     S: SerializeMap,
 {
     values: BTreeMap<&'a str, serde_json::Value>,
+    error_message: Option<String>,
     severity: LogSeverity,
+    event: &'a Event<'a>,
+    metadata: &'a Metadata<'a>,
+    formatter: &'a EventFormatter,
     serializer: S,
+    written: BTreeSet<String>,
 }
 
 impl<'a, S> Visitor<'a, S>
 where
     S: SerializeMap,
 {
-    /// Returns a new default visitor using the provided writer
-    pub(crate) fn new(severity: LogSeverity, serializer: S) -> Self {
+    /// Returns a new default visitor using the provided serializer and formatter configuration
+    pub(crate) fn new(
+        severity: LogSeverity,
+        event: &'a Event<'a>,
+        metadata: &'a Metadata<'a>,
+        serializer: S,
+        formatter: &'a EventFormatter,
+    ) -> Self {
         Self {
             values: BTreeMap::new(),
+            error_message: None,
             severity,
+            event,
+            metadata,
+            formatter,
             serializer,
+            written: BTreeSet::new(),
         }
     }
+
+    /// Writes a field of the LogEntry itself, whose key is never reused by other fields
+    pub(crate) fn write<V>(&mut self, key: &str, value: &V) -> Result<(), S::Error>
+    where
+        V: serde::Serialize + ?Sized,
+    {
+        self.written.insert(key.to_owned());
+        self.serializer.serialize_entry(key, value)
+    }
+
+    /// Records the value of an event field, unless the field is filtered out
+    fn insert(&mut self, field: &Field, value: serde_json::Value) {
+        if self.formatter.field_filter.allows(field.name()) {
+            self.values.insert(field.name(), value);
+        }
+    }
+
+    /// Records a default value for a field (e.g. inherited from the event's span context), which
+    /// is overridden by any event field of the same name
+    pub(crate) fn inherit(&mut self, key: &'a str, value: serde_json::Value) {
+        self.values.insert(key, value);
+    }
 }
 
 impl<'a, S> VisitOutput<fmt::Result> for Visitor<'a, S>
@@ -35,22 +107,280 @@ where
 {
     fn finish(mut self) -> fmt::Result {
         let inner = || {
-            let severity = self
+            // `#[instrument(err)]` records returned errors as the only field of an `ERROR` event,
+            // which is structured like any other error field (without any sources)
+            let is_instrumented_error = *self.metadata.level() == Level::ERROR
+                && self.metadata.fields().len() == 1
+                && self.metadata.fields().field("error").is_some();
+
+            if let (true, None, Some(serde_json::Value::String(error))) = (
+                is_instrumented_error,
+                &self.error_message,
+                self.values.get("error"),
+            ) {
+                let (message, value) =
+                    format_error(self.formatter.error_format, error.clone(), Vec::new());
+                self.error_message = Some(message);
+                self.values.insert("error", value);
+            }
+
+            // the metadata of forwarded `log` records is already written to the target and
+            // source location of the event, in place of their metadata fields
+            if self.event.is_log() {
+                for field in LOG_FIELDS {
+                    self.values.remove(field);
+                }
+            }
+
+            for (key, value) in self.values.iter_mut() {
+                self.formatter.field_filter.redact(key, value);
+
+                if self.formatter.control_char_handling != ControlCharHandling::Escape {
+                    replace_control_chars(value, self.formatter.control_char_handling);
+                }
+            }
+
+            // unknown severity overrides fall back to the Level-derived severity, keeping the
+            // unknown value alongside it
+            let severity = match self.values.remove("severity") {
+                Some(value) => match LogSeverity::from_value(&value) {
+                    Some(severity) => severity,
+                    None => {
+                        self.formatter.warn(format_args!(
+                            "unrecognized severity {}, falling back to {}",
+                            value, self.severity
+                        ));
+                        self.values.entry("severity_value").or_insert(value);
+                        self.severity
+                    }
+                },
+                None => self.severity,
+            };
+
+            match self.formatter.severity_format {
+                SeverityFormat::String => self.write("severity", &severity)?,
+                SeverityFormat::Number => self.write("severity", &i32::from(severity))?,
+                SeverityFormat::Both => {
+                    self.write("severity", &severity)?;
+                    self.write("severityNumber", &i32::from(severity))?;
+                }
+            }
+
+            // event fields (e.g. from the panic hook) override the service context of the layer
+            let event_service_context =
+                self.values
+                    .remove("service_context.service")
+                    .map(|service| {
+                        let mut service_context = serde_json::Map::new();
+                        service_context.insert("service".to_owned(), service);
+
+                        if let Some(version) = self.values.remove("service_context.version") {
+                            service_context.insert("version".to_owned(), version);
+                        }
+
+                        service_context
+                    });
+            let layer_service_context = self.formatter.service_context.as_ref();
+            let mut error_context = None;
+
+            let is_error = match &self.formatter.error_reporting_filter {
+                Some(error_reporting_filter) => error_reporting_filter(self.event, severity),
+                None => severity >= self.formatter.error_reporting_threshold,
+            };
+
+            let is_reported = (event_service_context.is_some()
+                || layer_service_context.is_some()
+                || self.formatter.report_errors)
+                && is_error;
+
+            if is_reported {
+                let metadata = self.metadata;
+
+                // event fields win any collisions with the error event type
+                if !self.values.contains_key("@type") {
+                    self.write("@type", REPORTED_ERROR_EVENT_TYPE)?;
+                }
+
+                match (&event_service_context, layer_service_context) {
+                    (Some(service_context), _) => self.write("serviceContext", service_context)?,
+                    (None, Some(service_context)) => {
+                        self.write("serviceContext", service_context)?
+                    }
+                    (None, None) => {}
+                }
+
+                // event fields win any collisions with the error context, and stack trace fields
+                // (e.g. from the panic hook) locate errors more precisely than their callsites
+                if !self.values.contains_key("context") {
+                    let report_location = match self.values.contains_key("stack_trace") {
+                        true => None,
+                        false => metadata.file().map(|file_path| ReportLocation {
+                            file_path,
+                            line_number: metadata.line(),
+                            function_name: metadata.module_path(),
+                        }),
+                    };
+
+                    // structured HttpRequests are recorded as a single object field
+                    let http_request = match self.values.get("http_request") {
+                        Some(serde_json::Value::Object(http_request)) => {
+                            error_http_request(|key| http_request.get(key))
+                        }
+                        _ => BTreeMap::new(),
+                    };
+
+                    error_context = Some(ErrorContext {
+                        report_location,
+                        http_request,
+                    });
+                }
+            }
+
+            // event fields (e.g. from the panic hook) win any collisions with captured backtraces
+            if let Some(threshold) = self.formatter.stack_trace_threshold {
+                if severity >= threshold && !self.values.contains_key("stack_trace") {
+                    let backtrace = Backtrace::force_capture();
+
+                    if backtrace.status() == BacktraceStatus::Captured {
+                        self.values
+                            .insert("stack_trace", backtrace.to_string().into());
+                    }
+                }
+            }
+
+            // error messages lead with the error, followed by the stack trace that Error
+            // Reporting parses from them
+            if let (true, true, Some(error_message), Some(serde_json::Value::String(stack_trace))) = (
+                self.formatter.combine_error_messages,
+                is_reported,
+                &self.error_message,
+                self.values.get("stack_trace"),
+            ) {
+                let message = format!("{}\n{}", error_message, stack_trace);
+                self.values.insert("message", message.into());
+            }
+
+            // payload fields are truncated once they're complete, including combined error messages
+            let mut truncated = Vec::new();
+
+            if let Some(max_field_bytes) = self.formatter.max_field_bytes {
+                for (key, value) in self.values.iter_mut() {
+                    if is_special(key) {
+                        continue;
+                    }
+
+                    if let serde_json::Value::String(value) = value {
+                        if truncate(value, max_field_bytes) {
+                            truncated.push(format!("{}_truncated", key).to_camel_case());
+                        }
+                    }
+                }
+            }
+
+            let trace = self
                 .values
-                .remove("severity")
-                .map(LogSeverity::from)
-                .unwrap_or(self.severity);
+                .remove("trace_id")
+                .map(|trace_id| format_trace(trace_id, self.formatter.project_id.as_deref()));
+            let span_id = self.values.remove("span_id").map(format_span_id);
+            let trace_sampled = self.values.remove("trace_sampled").and_then(coerce_bool);
 
-            self.serializer.serialize_entry("severity", &severity)?;
+            if let Some(trace) = &trace {
+                self.write("logging.googleapis.com/trace", trace)?;
+            }
+
+            if let Some(span_id) = &span_id {
+                self.write("logging.googleapis.com/spanId", span_id)?;
+            }
+
+            // sampling decisions are only meaningful for entries tied to a specific trace span
+            if let (Some(_), Some(_), Some(trace_sampled)) = (&trace, &span_id, trace_sampled) {
+                self.write("logging.googleapis.com/trace_sampled", &trace_sampled)?;
+            }
+
+            if !self.formatter.include_empty_messages
+                && self
+                    .values
+                    .get("message")
+                    .and_then(serde_json::Value::as_str)
+                    == Some("")
+            {
+                self.values.remove("message");
+            }
+
+            // event fields (by their camelCased keys) win any collisions with a custom message key
+            let message_field = self.formatter.message_field.as_str();
+            let custom_message = match message_field != "message"
+                && !self
+                    .values
+                    .keys()
+                    .any(|key| key.to_camel_case() == message_field)
+            {
+                true => self.values.remove("message"),
+                false => None,
+            };
 
             let mut http_request = BTreeMap::new();
             let mut labels = BTreeMap::new();
+            let mut operation = BTreeMap::new();
+            let mut nested = serde_json::Map::new();
+            let mut fields = Vec::new();
 
-            for (key, value) in self.values {
+            for (key, value) in std::mem::take(&mut self.values) {
                 let mut key_segments = key.splitn(2, '.');
 
                 match (key_segments.next(), key_segments.next()) {
+                    // structured HttpRequests share the `httpRequest` of any dotted fields, which
+                    // are visited after them (and so take precedence)
+                    (Some("http_request"), None) if value.is_object() => {
+                        if let serde_json::Value::Object(value) = value {
+                            http_request.extend(value);
+                        }
+                    }
+                    (Some("http_request"), Some(request_key @ ("latency_ms" | "latency_us"))) => {
+                        let scale = match request_key {
+                            "latency_ms" => 1_000.0,
+                            _ => 1_000_000.0,
+                        };
+
+                        // integers convert losslessly, while fractional values are rounded to
+                        // the nearest nanosecond
+                        let latency = match (request_key, value.as_u64(), value.as_f64()) {
+                            ("latency_ms", Some(latency), _) => {
+                                Some(Duration::from_millis(latency))
+                            }
+                            (_, Some(latency), _) => Some(Duration::from_micros(latency)),
+                            (_, None, Some(latency)) => {
+                                Duration::try_from_secs_f64(latency / scale).ok()
+                            }
+                            (_, None, None) => None,
+                        };
+
+                        // invalid latencies are dropped, since they aren't HttpRequest fields
+                        let Some(latency) = latency else {
+                            self.formatter.warn(format_args!(
+                                "invalid http_request.{} {}, expected a non-negative number",
+                                request_key, value
+                            ));
+                            continue;
+                        };
+
+                        // explicit latencies win any collisions with numeric ones
+                        http_request
+                            .entry("latency".to_owned())
+                            .or_insert_with(|| format_duration(latency).into());
+                    }
                     (Some("http_request"), Some(request_key)) => {
+                        if request_key == "latency" && self.formatter.validate_latency {
+                            match &value {
+                                serde_json::Value::String(latency) if is_duration(latency) => {}
+                                _ => self.formatter.warn(format_args!(
+                                    "invalid http_request.latency {}, expected a duration like \
+                                     \"0.23s\"",
+                                    value
+                                )),
+                            }
+                        }
+
                         http_request.insert(request_key.to_camel_case(), value);
                     }
                     (Some("labels"), Some(label_key)) => {
@@ -61,32 +391,126 @@ where
 
                         labels.insert(label_key.to_camel_case(), value);
                     }
+                    (Some("operation"), Some(operation_key)) => {
+                        let value = match operation_key {
+                            "first" | "last" => match coerce_bool(value) {
+                                Some(value) => serde_json::Value::from(value),
+                                None => continue,
+                            },
+                            _ => value,
+                        };
+
+                        operation.insert(operation_key.to_camel_case(), value);
+                    }
                     (Some("insert_id"), None) => {
                         let value = match value {
                             serde_json::Value::String(value) => value,
                             _ => value.to_string(),
                         };
 
-                        self.serializer
-                            .serialize_entry("logging.googleapis.com/insertId", &value)?;
+                        self.write("logging.googleapis.com/insertId", &value)?;
+                    }
+                    // type annotations and stack traces are written verbatim rather than
+                    // camelCase-d, since Error Reporting only recognizes their original keys
+                    (Some(key @ ("@type" | "stack_trace")), None) => self.write(key, &value)?,
+                    _ if self.formatter.nest_dotted_fields => {
+                        let path: Vec<_> = key.split('.').map(Inflector::to_camel_case).collect();
+                        insert_nested(self.formatter, &mut nested, &path, value);
                     }
-                    (Some(key), None) => self
-                        .serializer
-                        .serialize_entry(&key.to_camel_case(), &value)?,
-                    _ => self
-                        .serializer
-                        .serialize_entry(&key.to_camel_case(), &value)?,
+                    _ => fields.push((key.to_camel_case(), value)),
                 }
             }
 
+            fields.extend(nested);
+
+            let error_context = error_context
+                .map(|mut error_context| {
+                    if error_context.http_request.is_empty() {
+                        error_context.http_request =
+                            error_http_request(|key| http_request.get(key));
+                    }
+
+                    error_context
+                })
+                .filter(|error_context| {
+                    error_context.report_location.is_some()
+                        || !error_context.http_request.is_empty()
+                });
+
+            // keys written by the layer after the event's own fields, which take precedence
+            let mut written = self.written.clone();
+            written.extend(truncated.iter().cloned());
+
+            if error_context.is_some() {
+                written.insert("context".to_owned());
+            }
+
             if !http_request.is_empty() {
-                self.serializer
-                    .serialize_entry("httpRequest", &http_request)?;
+                written.insert("httpRequest".to_owned());
+            }
+
+            if !operation.is_empty() {
+                written.insert("logging.googleapis.com/operation".to_owned());
+            }
+
+            if !labels.is_empty() || !self.formatter.labels.is_empty() {
+                written.insert("logging.googleapis.com/labels".to_owned());
+            }
+
+            // custom message keys never shadow the layer's own fields
+            if let Some(message) = custom_message {
+                if written.insert(message_field.to_owned()) {
+                    self.write(message_field, &message)?;
+                } else {
+                    self.formatter.warn(format_args!(
+                        "message field {} is written by the layer itself, writing the message to \
+                         message instead",
+                        message_field
+                    ));
+                    fields.push(("message".to_owned(), message));
+                }
+            }
+
+            for (key, value) in unique_keys(written, fields) {
+                self.write(&key, &value)?;
             }
 
-            if !labels.is_empty() {
-                self.serializer
-                    .serialize_entry("logging.googleapis.com/labels", &labels)?;
+            for key in &truncated {
+                self.write(key, &true)?;
+            }
+
+            if let Some(error_context) = error_context {
+                self.write("context", &error_context)?;
+            }
+
+            if !http_request.is_empty() {
+                self.write("httpRequest", &http_request)?;
+            }
+
+            if !operation.is_empty() {
+                self.write("logging.googleapis.com/operation", &operation)?;
+            }
+
+            // static labels are borrowed rather than rebuilt, and only merged when necessary
+            if labels.is_empty() {
+                if !self.formatter.labels.is_empty() {
+                    self.write("logging.googleapis.com/labels", &self.formatter.labels)?;
+                }
+            } else {
+                let mut merged: BTreeMap<&str, &str> = self
+                    .formatter
+                    .labels
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value.as_str()))
+                    .collect();
+
+                merged.extend(
+                    labels
+                        .iter()
+                        .map(|(key, value)| (key.as_str(), value.as_str())),
+                );
+
+                self.write("logging.googleapis.com/labels", &merged)?;
             }
 
             self.serializer.end()
@@ -100,42 +524,357 @@ where
     }
 }
 
+/// Fields of an `httpRequest` (by their LogEntry names) that Error Reporting displays, along with
+/// their names in Error Reporting's
+/// [`HttpRequestContext`](https://cloud.google.com/error-reporting/reference/rest/v1beta1/ErrorContext#HttpRequestContext)
+const ERROR_HTTP_REQUEST_FIELDS: &[(&str, &str)] = &[
+    ("requestMethod", "method"),
+    ("requestUrl", "url"),
+    ("status", "responseStatusCode"),
+    ("userAgent", "userAgent"),
+    ("remoteIp", "remoteIp"),
+    ("referer", "referrer"),
+];
+
+/// Project the fields of an `httpRequest` that are present onto an Error Reporting
+/// `HttpRequestContext`
+fn error_http_request<'v>(
+    field: impl Fn(&str) -> Option<&'v serde_json::Value>,
+) -> BTreeMap<&'static str, serde_json::Value> {
+    ERROR_HTTP_REQUEST_FIELDS
+        .iter()
+        .filter_map(|(key, context_key)| Some((*context_key, field(key)?.clone())))
+        .collect()
+}
+
+/// Rename (camelCased) event fields that collide with the keys written by the layer itself for
+/// the event (or with the other fields of the event) to `<key>Value`, repeatedly if that collides
+/// as well, so that no key is written twice. Since the layer's fields always take precedence,
+/// fields that don't collide keep their keys before any colliding fields are renamed.
+fn unique_keys(
+    mut written: BTreeSet<String>,
+    fields: Vec<(String, serde_json::Value)>,
+) -> Vec<(String, serde_json::Value)> {
+    let colliding: Vec<bool> = fields
+        .iter()
+        .map(|(key, _)| !written.insert(key.clone()))
+        .collect();
+
+    fields
+        .into_iter()
+        .zip(colliding)
+        .map(|((mut key, value), colliding)| {
+            if colliding {
+                while written.contains(&key) {
+                    key.push_str("Value");
+                }
+
+                written.insert(key.clone());
+            }
+
+            (key, value)
+        })
+        .collect()
+}
+
+/// Format an error and its chain of sources, returning both the error's message and the value of
+/// its field (which is the message itself for `ErrorFormat::MultiLine`)
+fn format_error(
+    error_format: ErrorFormat,
+    error: String,
+    causes: Vec<String>,
+) -> (String, serde_json::Value) {
+    match error_format {
+        ErrorFormat::Structured => {
+            let message = std::iter::once(error)
+                .chain(causes.iter().cloned())
+                .collect::<Vec<_>>()
+                .join(": ");
+
+            let value = serde_json::json!({
+                "message": message,
+                "causes": causes,
+            });
+
+            (message, value)
+        }
+        ErrorFormat::MultiLine => {
+            let message = std::iter::once(error)
+                .chain(causes.iter().map(|cause| format!("caused by: {}", cause)))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            (message.clone(), message.into())
+        }
+    }
+}
+
+/// Marker appended to truncated field values
+const TRUNCATION_MARKER: &str = "…";
+
+/// Strip or replace the ASCII control characters (other than tabs and line breaks) of every
+/// string within a value
+fn replace_control_chars(
+    value: &mut serde_json::Value,
+    control_char_handling: ControlCharHandling,
+) {
+    let is_control = |char: char| char.is_ascii_control() && !matches!(char, '\t' | '\n' | '\r');
+
+    match value {
+        serde_json::Value::String(value) if value.contains(is_control) => {
+            *value = match control_char_handling {
+                ControlCharHandling::Escape => return,
+                ControlCharHandling::Strip => {
+                    value.chars().filter(|char| !is_control(*char)).collect()
+                }
+                ControlCharHandling::Replace(replacement) => value
+                    .chars()
+                    .map(|char| match is_control(char) {
+                        true => replacement,
+                        false => char,
+                    })
+                    .collect(),
+            };
+        }
+        serde_json::Value::Array(values) => {
+            for value in values {
+                replace_control_chars(value, control_char_handling);
+            }
+        }
+        serde_json::Value::Object(values) => {
+            for value in values.values_mut() {
+                replace_control_chars(value, control_char_handling);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether or not a field is mapped to a special LogEntry field rather than the Event's payload
+fn is_special(key: &str) -> bool {
+    SPECIAL_FIELDS.contains(&key)
+        || SPECIAL_PREFIXES
+            .iter()
+            .any(|prefix| key.starts_with(prefix))
+}
+
+/// Truncate a string to at most `max_bytes` bytes (on a character boundary), including a
+/// truncation marker when there's room for one, returning whether or not it was truncated
+fn truncate(value: &mut String, max_bytes: usize) -> bool {
+    if value.len() <= max_bytes {
+        return false;
+    }
+
+    let marker = match max_bytes >= TRUNCATION_MARKER.len() {
+        true => TRUNCATION_MARKER,
+        false => "",
+    };
+
+    let boundary = (0..=max_bytes - marker.len())
+        .rev()
+        .find(|index| value.is_char_boundary(*index))
+        .unwrap_or_default();
+
+    value.truncate(boundary);
+    value.push_str(marker);
+    true
+}
+
+/// Insert a value at a path of nested objects, creating intermediate objects as needed. Objects
+/// win any collisions with other values at the same path, which are reported to the Layer's error
+/// handler.
+fn insert_nested(
+    formatter: &EventFormatter,
+    fields: &mut serde_json::Map<String, serde_json::Value>,
+    path: &[String],
+    value: serde_json::Value,
+) {
+    let Some((key, rest)) = path.split_first() else {
+        return;
+    };
+
+    if rest.is_empty() {
+        match fields.get(key) {
+            Some(serde_json::Value::Object(_)) if !value.is_object() => {
+                formatter.warn(format_args!("field {} collides with a nested object", key))
+            }
+            _ => {
+                fields.insert(key.clone(), value);
+            }
+        }
+
+        return;
+    }
+
+    let entry = fields
+        .entry(key.clone())
+        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+
+    if !entry.is_object() {
+        formatter.warn(format_args!("field {} collides with a nested object", key));
+        *entry = serde_json::Value::Object(serde_json::Map::new());
+    }
+
+    if let serde_json::Value::Object(entry) = entry {
+        insert_nested(formatter, entry, rest, value);
+    }
+}
+
+/// Qualify a trace ID as a Cloud Trace resource name when a project ID is available,
+/// passing it through verbatim otherwise
+fn format_trace(trace_id: serde_json::Value, project_id: Option<&str>) -> String {
+    let trace_id = match trace_id {
+        serde_json::Value::String(trace_id) => trace_id,
+        _ => trace_id.to_string(),
+    };
+
+    match project_id {
+        Some(project_id) if !trace_id.starts_with("projects/") => {
+            format!("projects/{project_id}/traces/{trace_id}")
+        }
+        _ => trace_id,
+    }
+}
+
+/// Format a span ID as the 16-character hex string expected by Cloud Trace
+fn format_span_id(span_id: serde_json::Value) -> String {
+    match span_id {
+        // short hex span IDs are zero-padded to 16 characters
+        serde_json::Value::String(span_id)
+            if span_id.len() < 16 && span_id.chars().all(|char| char.is_ascii_hexdigit()) =>
+        {
+            format!("{:0>16}", span_id)
+        }
+        serde_json::Value::String(span_id) => span_id,
+        // numeric span IDs are formatted as 16-character hex strings
+        serde_json::Value::Number(span_id) => match span_id.as_u64() {
+            Some(span_id) => format!("{:016x}", span_id),
+            None => span_id.to_string(),
+        },
+        _ => span_id.to_string(),
+    }
+}
+
+/// Whether or not a string is a valid protobuf JSON `Duration`, e.g. `3s` or `-0.000001s`
+fn is_duration(value: &str) -> bool {
+    let value = value.strip_prefix('-').unwrap_or(value);
+
+    let Some(value) = value.strip_suffix('s') else {
+        return false;
+    };
+
+    let (seconds, nanos) = value.split_once('.').unwrap_or((value, "0"));
+
+    let is_digits = |digits: &str| digits.chars().all(|char| char.is_ascii_digit());
+
+    !seconds.is_empty() && is_digits(seconds) && (1..=9).contains(&nanos.len()) && is_digits(nanos)
+}
+
+/// Coerce boolean and stringly-typed boolean values to a proper boolean
+fn coerce_bool(value: serde_json::Value) -> Option<bool> {
+    match value {
+        serde_json::Value::Bool(value) => Some(value),
+        serde_json::Value::String(value) => value.parse().ok(),
+        _ => None,
+    }
+}
+
 impl<'a, S> Visit for Visitor<'a, S>
 where
     S: SerializeMap,
 {
     fn record_i64(&mut self, field: &Field, value: i64) {
-        self.values
-            .insert(field.name(), serde_json::Value::from(value));
+        self.insert(field, serde_json::Value::from(value));
     }
 
     fn record_u64(&mut self, field: &Field, value: u64) {
-        self.values
-            .insert(field.name(), serde_json::Value::from(value));
+        self.insert(field, serde_json::Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        // JSON has no representation for NaN or infinite numbers, so they're kept as strings
+        let value = match serde_json::Number::from_f64(value) {
+            Some(number) => serde_json::Value::Number(number),
+            None => serde_json::Value::from(value.to_string()),
+        };
+
+        self.insert(field, value);
+    }
+
+    fn record_u128(&mut self, field: &Field, value: u128) {
+        let value = match field.name() {
+            // 16-byte trace IDs are formatted as the 32-character hex strings Cloud Trace expects
+            "trace_id" => format!("{:032x}", value),
+            _ => value.to_string(),
+        };
+
+        self.insert(field, serde_json::Value::from(value));
     }
 
     fn record_bool(&mut self, field: &Field, value: bool) {
-        self.values
-            .insert(field.name(), serde_json::Value::from(value));
+        self.insert(field, serde_json::Value::from(value));
     }
 
     fn record_str(&mut self, field: &Field, value: &str) {
-        self.values
-            .insert(field.name(), serde_json::Value::from(value));
+        self.insert(field, serde_json::Value::from(value));
     }
 
     fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
-        self.values.insert(
-            field.name(),
-            serde_json::Value::from(format!("{:?}", value)),
-        );
+        let mut debug = String::new();
+
+        let value = match fmt::Write::write_fmt(&mut debug, format_args!("{:?}", value)) {
+            Ok(()) => serde_json::Value::from(debug),
+            Err(_) => serde_json::Value::from(UNSERIALIZABLE),
+        };
+
+        self.insert(field, value);
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        if !self.formatter.field_filter.allows(field.name()) {
+            return;
+        }
+
+        let causes: Vec<String> = match value.downcast_ref::<ErrorValue>() {
+            Some(error_value) => {
+                // event fields win any collisions with the backtraces of error reports
+                if let Some(backtrace) = &error_value.backtrace {
+                    self.values
+                        .entry("stack_trace")
+                        .or_insert_with(|| backtrace.as_str().into());
+                }
+
+                error_value
+                    .causes
+                    .iter()
+                    .take(self.formatter.error_chain_depth)
+                    .cloned()
+                    .collect()
+            }
+            None => std::iter::successors(value.source(), |error| error.source())
+                .take(self.formatter.error_chain_depth)
+                .map(ToString::to_string)
+                .collect(),
+        };
+
+        let (message, value) = format_error(self.formatter.error_format, value.to_string(), causes);
+
+        // the first error field is the one that combined error messages lead with
+        self.error_message.get_or_insert(message);
+        self.insert(field, value);
     }
 
     #[cfg(all(tracing_unstable, feature = "valuable"))]
     fn record_value(&mut self, field: &Field, value: valuable::Value<'_>) {
-        let value = serde_json::to_value(valuable_serde::Serializable::new(value)).unwrap();
+        if let Some(duration) = crate::duration_value::as_duration(&value) {
+            let value = crate::google::duration_value(duration, self.formatter.duration_format);
+            return self.insert(field, value);
+        }
+
+        let value = serde_json::to_value(valuable_serde::Serializable::new(value))
+            .unwrap_or_else(|_| serde_json::Value::from(UNSERIALIZABLE));
 
-        self.values.insert(field.name(), value);
+        self.insert(field, value);
     }
 }
 
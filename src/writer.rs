@@ -1,7 +1,13 @@
 use std::{
     fmt::{Formatter, Write},
     io,
+    sync::{Arc, Mutex},
 };
+use tracing_core::Metadata;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Handler for the errors of a Layer's writers and the warnings of its event formatter
+pub(crate) type ErrorHandler = Arc<dyn Fn(io::Error) + Send + Sync>;
 
 /// Utility newtype for converting between fmt::Write and io::Write
 // https://docs.rs/tracing-subscriber/latest/src/tracing_subscriber/fmt/writer.rs.html
@@ -20,11 +26,9 @@ impl<'a> io::Write for WriteAdaptor<'a> {
         let s =
             std::str::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
 
-        self.fmt_write
-            .write_str(s)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        self.fmt_write.write_str(s).map_err(io::Error::other)?;
 
-        Ok(s.as_bytes().len())
+        Ok(s.len())
     }
 
     fn flush(&mut self) -> io::Result<()> {
@@ -37,3 +41,123 @@ impl<'a> std::fmt::Debug for WriteAdaptor<'a> {
         formatter.pad("WriteAdaptor { .. }")
     }
 }
+
+/// MakeWriter that passes the errors of its writers to an error handler (configured with
+/// `Layer::on_error`) instead of the Layer, which would otherwise report them on `stderr`. Every
+/// MakeWriter of a Layer is wrapped in one, so that the handler is kept when the MakeWriter
+/// changes.
+#[derive(Clone)]
+pub struct WithErrorHandler<M> {
+    make_writer: M,
+    handler: Option<ErrorHandler>,
+}
+
+impl<M> WithErrorHandler<M> {
+    pub(crate) fn new(make_writer: M, handler: Option<ErrorHandler>) -> Self {
+        Self {
+            make_writer,
+            handler,
+        }
+    }
+
+    /// Returns the error handler of the MakeWriter, if any
+    pub(crate) fn handler(&self) -> Option<ErrorHandler> {
+        self.handler.clone()
+    }
+
+    /// Replaces the error handler of the MakeWriter
+    pub(crate) fn with_handler(self, handler: ErrorHandler) -> Self {
+        Self {
+            handler: Some(handler),
+            ..self
+        }
+    }
+}
+
+impl<M> std::fmt::Debug for WithErrorHandler<M>
+where
+    M: std::fmt::Debug,
+{
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("WithErrorHandler")
+            .field("make_writer", &self.make_writer)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, M> MakeWriter<'a> for WithErrorHandler<M>
+where
+    M: MakeWriter<'a>,
+{
+    type Writer = ErrorHandlingWriter<'a, M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        ErrorHandlingWriter {
+            writer: self.make_writer.make_writer(),
+            handler: self.handler.as_ref(),
+        }
+    }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        ErrorHandlingWriter {
+            writer: self.make_writer.make_writer_for(meta),
+            handler: self.handler.as_ref(),
+        }
+    }
+}
+
+/// Writer that passes its errors to an error handler (if any), after which the rest of the Event
+/// being written is dropped
+pub struct ErrorHandlingWriter<'a, W> {
+    writer: W,
+    handler: Option<&'a ErrorHandler>,
+}
+
+impl<'a, W> io::Write for ErrorHandlingWriter<'a, W>
+where
+    W: io::Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match (self.writer.write(buf), self.handler) {
+            // interrupted writes are retried by the Layer
+            (Err(error), Some(handler)) if error.kind() != io::ErrorKind::Interrupted => {
+                handler(error);
+                Ok(buf.len())
+            }
+            (result, _) => result,
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match (self.writer.flush(), self.handler) {
+            (Err(error), Some(handler)) => {
+                handler(error);
+                Ok(())
+            }
+            (result, _) => result,
+        }
+    }
+}
+
+impl<'a, W> std::fmt::Debug for ErrorHandlingWriter<'a, W> {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        formatter.pad("ErrorHandlingWriter { .. }")
+    }
+}
+
+/// Writer that appends to a shared in-memory buffer, for capturing Events as JSON
+pub(crate) struct BufferWriter(pub(crate) Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for BufferWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+#![cfg(feature = "anyhow")]
+use anyhow::Context;
+use serde::Deserialize;
+use tracing_stackdriver::error_value;
+
+#[derive(Debug, Deserialize)]
+struct MockError {
+    message: String,
+    causes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithError {
+    #[serde(rename = "@type")]
+    r#type: Option<String>,
+    error: MockError,
+    stack_trace: Option<String>,
+}
+
+fn load_config() -> anyhow::Result<()> {
+    Err(anyhow::anyhow!("file not found"))
+        .context("couldn't read config.toml")
+        .context("couldn't load config")
+}
+
+#[test]
+fn records_anyhow_error_chains_and_backtraces() {
+    std::env::set_var("RUST_LIB_BACKTRACE", "1");
+    let error = load_config().expect_err("Config unexpectedly loaded");
+
+    let events = tracing_stackdriver::layer()
+        .with_report_errors(true)
+        .as_json(|| {
+            tracing::error!(
+                error = &error_value(&error) as &dyn std::error::Error,
+                "startup failed"
+            )
+        })
+        .expect("Error converting events to JSON");
+
+    let event: MockEventWithError =
+        serde_json::from_value(events[0].clone()).expect("Error deserializing event");
+
+    assert!(event.r#type.is_some());
+    assert_eq!(
+        event.error.message,
+        "couldn't load config: couldn't read config.toml: file not found"
+    );
+    assert_eq!(
+        event.error.causes,
+        ["couldn't read config.toml", "file not found"]
+    );
+    assert!(event
+        .stack_trace
+        .expect("No stack_trace found")
+        .contains("load_config"));
+}
@@ -0,0 +1,35 @@
+#[test]
+fn returns_events_as_json_values() {
+    let events = tracing_stackdriver::layer()
+        .with_project_id("my-proj")
+        .as_json(|| {
+            let span = tracing::info_span!("request", trace_id = "abc123");
+            let _span = span.enter();
+            tracing::warn!(user_id = 42, "slow request");
+        })
+        .expect("Error converting events to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["severity"], "WARNING");
+    assert_eq!(event["message"], "slow request");
+    assert_eq!(event["userId"], 42);
+    assert_eq!(event["span"]["name"], "request");
+    assert_eq!(
+        event["logging.googleapis.com/trace"],
+        "projects/my-proj/traces/abc123"
+    );
+}
+
+#[test]
+fn returns_pretty_printed_events_as_json_values() {
+    let events = tracing_stackdriver::layer()
+        .with_pretty(true)
+        .as_json(|| {
+            tracing::info!("first");
+            tracing::info!("second");
+        })
+        .expect("Error converting events to JSON");
+
+    let messages: Vec<_> = events.iter().map(|event| &event["message"]).collect();
+    assert_eq!(messages, ["first", "second"]);
+}
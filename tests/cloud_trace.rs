@@ -0,0 +1,382 @@
+use helpers::{run_with_tracing, run_with_tracing_layer};
+use serde::Deserialize;
+
+mod helpers;
+mod mocks;
+
+static PROJECT_ID: &str = "my-proj";
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithTrace {
+    #[serde(rename = "logging.googleapis.com/trace")]
+    trace: String,
+}
+
+#[test]
+fn qualifies_string_trace_ids_with_project_id() {
+    let trace_id = "abc123def4567890abc123def4567890";
+    let layer = tracing_stackdriver::layer().with_project_id(PROJECT_ID);
+
+    let events =
+        run_with_tracing_layer::<MockEventWithTrace>(layer, || tracing::info!(trace_id, "hello!"))
+            .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(
+        event.trace,
+        format!("projects/{PROJECT_ID}/traces/{trace_id}")
+    );
+}
+
+#[test]
+fn qualifies_binary_trace_ids_with_project_id() {
+    let trace_id: u128 = 0x0af7651916cd43dd8448eb211c80319c;
+    let layer = tracing_stackdriver::layer().with_project_id(PROJECT_ID);
+
+    let events =
+        run_with_tracing_layer::<MockEventWithTrace>(layer, || tracing::info!(trace_id, "hello!"))
+            .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(
+        event.trace,
+        format!("projects/{PROJECT_ID}/traces/0af7651916cd43dd8448eb211c80319c")
+    );
+}
+
+#[test]
+fn passes_trace_ids_through_without_project_id() {
+    let trace_id = "projects/another-proj/traces/abc123";
+
+    let events = run_with_tracing::<MockEventWithTrace>(|| tracing::info!(trace_id, "hello!"))
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.trace, trace_id);
+}
+
+#[derive(Debug, Deserialize)]
+struct MockSpanWithTrace {
+    trace_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithSpanTrace {
+    #[serde(rename = "logging.googleapis.com/trace")]
+    trace: String,
+    span: MockSpanWithTrace,
+}
+
+#[test]
+fn promotes_span_trace_ids() {
+    let trace_id = "abc123def4567890abc123def4567890";
+    let layer = tracing_stackdriver::layer().with_project_id(PROJECT_ID);
+
+    let events = run_with_tracing_layer::<MockEventWithSpanTrace>(layer, || {
+        let span = tracing::info_span!("request", trace_id);
+        let _guard = span.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(
+        event.trace,
+        format!("projects/{PROJECT_ID}/traces/{trace_id}")
+    );
+    assert_eq!(event.span.trace_id, trace_id);
+}
+
+#[test]
+fn prefers_event_trace_ids_over_span_trace_ids() {
+    let layer = tracing_stackdriver::layer().with_project_id(PROJECT_ID);
+
+    let events = run_with_tracing_layer::<MockEventWithTrace>(layer, || {
+        let span = tracing::info_span!("request", trace_id = "span-trace");
+        let _guard = span.enter();
+        tracing::info!(trace_id = "event-trace", "hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(
+        event.trace,
+        format!("projects/{PROJECT_ID}/traces/event-trace")
+    );
+}
+
+#[test]
+fn omits_trace_by_default() {
+    let events = run_with_tracing::<serde_json::Map<String, serde_json::Value>>(|| {
+        let span = tracing::info_span!("request", foo = "bar");
+        let _guard = span.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert!(!event.contains_key("logging.googleapis.com/trace"));
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithSpanId {
+    #[serde(rename = "logging.googleapis.com/spanId")]
+    span_id: String,
+}
+
+#[test]
+fn promotes_string_span_ids() {
+    let span_id = "000000000000004a";
+
+    let events = run_with_tracing::<MockEventWithSpanId>(|| {
+        let span = tracing::info_span!("request", span_id);
+        let _guard = span.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.span_id, span_id);
+}
+
+#[test]
+fn formats_numeric_span_ids_as_hex() {
+    let span_id: u64 = 74;
+
+    let events = run_with_tracing::<MockEventWithSpanId>(|| {
+        let span = tracing::info_span!("request", span_id);
+        let _guard = span.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.span_id, "000000000000004a");
+}
+
+#[test]
+fn omits_span_id_by_default() {
+    let events = run_with_tracing::<serde_json::Map<String, serde_json::Value>>(|| {
+        let span = tracing::info_span!("request", foo = "bar");
+        let _guard = span.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert!(!event.contains_key("logging.googleapis.com/spanId"));
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithSpanAndSpanId {
+    #[serde(rename = "logging.googleapis.com/spanId")]
+    span_id: String,
+    span: mocks::MockSpan,
+}
+
+#[test]
+fn pads_event_span_ids_alongside_span() {
+    let events = run_with_tracing::<MockEventWithSpanAndSpanId>(|| {
+        let span = tracing::info_span!("stackdriver_span", foo = "bar");
+        let _guard = span.enter();
+        tracing::info!(span_id = "4a", "hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.span_id, "000000000000004a");
+    assert_eq!(event.span.name, "stackdriver_span");
+    assert_eq!(event.span.foo, "bar");
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithTraceSampled {
+    #[serde(rename = "logging.googleapis.com/trace_sampled")]
+    trace_sampled: Option<bool>,
+}
+
+#[test]
+fn promotes_span_trace_sampled_flags() {
+    let events = run_with_tracing::<MockEventWithTraceSampled>(|| {
+        let span = tracing::info_span!(
+            "request",
+            trace_id = "abc123",
+            span_id = "000000000000004a",
+            trace_sampled = true
+        );
+        let _guard = span.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.trace_sampled, Some(true));
+}
+
+#[test]
+fn coerces_stringly_trace_sampled_flags() {
+    let events = run_with_tracing::<MockEventWithTraceSampled>(|| {
+        let span = tracing::info_span!(
+            "request",
+            trace_id = "abc123",
+            span_id = "000000000000004a",
+            trace_sampled = "false"
+        );
+        let _guard = span.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.trace_sampled, Some(false));
+}
+
+#[test]
+fn omits_trace_sampled_without_trace_and_span_ids() {
+    let events = run_with_tracing::<MockEventWithTraceSampled>(|| {
+        let span = tracing::info_span!("request", trace_id = "abc123", trace_sampled = true);
+        let _guard = span.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.trace_sampled, None);
+}
+
+#[test]
+fn includes_event_trace_sampled_flags() {
+    let events = run_with_tracing::<MockEventWithTraceSampled>(|| {
+        tracing::info!(
+            trace_id = "abc123",
+            span_id = "000000000000004a",
+            trace_sampled = true,
+            "hello!"
+        );
+        tracing::info!(
+            trace_id = "abc123",
+            span_id = "000000000000004a",
+            trace_sampled = false,
+            "hello again!"
+        );
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let mut events = events.into_iter();
+    let sampled = events.next().expect("No sampled event heard");
+    let unsampled = events.next().expect("No unsampled event heard");
+    assert_eq!(sampled.trace_sampled, Some(true));
+    assert_eq!(unsampled.trace_sampled, Some(false));
+}
+
+#[test]
+fn passes_raw_trace_ids_through_without_project_id() {
+    let trace_id = "abc123def4567890abc123def4567890";
+
+    let events = run_with_tracing::<MockEventWithTrace>(|| tracing::info!(trace_id, "hello!"))
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.trace, trace_id);
+}
+
+#[test]
+fn detects_project_id_from_environment() {
+    std::env::set_var("GOOGLE_CLOUD_PROJECT", "detected-proj");
+    let layer = tracing_stackdriver::layer().with_auto_project_id();
+    std::env::remove_var("GOOGLE_CLOUD_PROJECT");
+
+    let events = run_with_tracing_layer::<MockEventWithTrace>(layer, || {
+        tracing::info!(trace_id = "abc123", "hello!")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.trace, "projects/detected-proj/traces/abc123");
+}
+
+#[test]
+fn promotes_ancestor_span_trace_fields() {
+    let layer = tracing_stackdriver::layer().with_project_id(PROJECT_ID);
+
+    let events = run_with_tracing_layer::<MockEventWithTraceContext>(layer, || {
+        let request = tracing::info_span!(
+            "request",
+            trace_id = "abc123",
+            span_id = "000000000000004a",
+            trace_sampled = true
+        );
+        let _request = request.enter();
+        let db_query = tracing::info_span!("db_query", span_id = "000000000000004b");
+        let _db_query = db_query.enter();
+        let retry = tracing::info_span!("retry");
+        let _retry = retry.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.trace, format!("projects/{PROJECT_ID}/traces/abc123"));
+    assert_eq!(event.span_id, "000000000000004b");
+    assert_eq!(event.trace_sampled, Some(true));
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithTraceContext {
+    #[serde(rename = "logging.googleapis.com/trace")]
+    trace: String,
+    #[serde(rename = "logging.googleapis.com/spanId")]
+    span_id: String,
+    #[serde(rename = "logging.googleapis.com/trace_sampled")]
+    trace_sampled: Option<bool>,
+}
+
+#[test]
+fn only_promotes_trace_fields_of_ancestor_spans() {
+    let events = run_with_tracing::<serde_json::Map<String, serde_json::Value>>(|| {
+        let request = tracing::info_span!("request", trace_id = "abc123", labels.user = "alex");
+        let _request = request.enter();
+        let db_query = tracing::info_span!("db_query", foo = "bar");
+        let _db_query = db_query.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["logging.googleapis.com/trace"], "abc123");
+    assert!(!event.contains_key("logging.googleapis.com/labels"));
+}
+
+#[test]
+fn falls_back_to_tracing_span_ids() {
+    let layer = tracing_stackdriver::layer().with_span_id_fallback(true);
+    let mut expected_span_id = None;
+
+    let events = run_with_tracing_layer::<MockEventWithSpanId>(layer, || {
+        let span = tracing::info_span!("request");
+        expected_span_id = span.id().map(|id| format!("{:016x}", id.into_u64()));
+        let _guard = span.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(Some(event.span_id.clone()), expected_span_id);
+}
+
+#[test]
+fn prefers_recorded_span_ids_over_fallback_span_ids() {
+    let layer = tracing_stackdriver::layer().with_span_id_fallback(true);
+
+    let events = run_with_tracing_layer::<MockEventWithSpanId>(layer, || {
+        let span = tracing::info_span!("request", span_id = "000000000000004a");
+        let _guard = span.enter();
+        let inner = tracing::info_span!("inner");
+        let _inner = inner.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.span_id, "000000000000004a");
+}
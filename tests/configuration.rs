@@ -0,0 +1,83 @@
+use helpers::run_with_tracing_layer;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use tracing::Level;
+use tracing_stackdriver::{LogSeverity, Stackdriver};
+
+mod helpers;
+mod mocks;
+
+#[derive(Debug, Deserialize)]
+struct MockConfiguredEvent {
+    severity: String,
+    #[serde(rename = "logging.googleapis.com/trace")]
+    trace: String,
+    #[serde(rename = "logging.googleapis.com/labels")]
+    labels: BTreeMap<String, String>,
+    #[serde(rename = "logging.googleapis.com/insertId")]
+    insert_id: String,
+    #[serde(rename = "logging.googleapis.com/sourceLocation")]
+    source_location: Option<mocks::MockSourceLocation>,
+}
+
+#[test]
+fn combines_layer_configuration() {
+    let layer = tracing_stackdriver::layer()
+        .with_project_id("my-proj")
+        .with_labels([("service", "checkout")])
+        .with_source_location(false)
+        .with_auto_insert_id(true)
+        .with_severity_mapping(|level| match *level {
+            Level::INFO => LogSeverity::Notice,
+            _ => LogSeverity::from(level),
+        });
+
+    let events = run_with_tracing_layer::<MockConfiguredEvent>(layer, || {
+        tracing::info!(trace_id = "abc123", "hello!")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.severity, "NOTICE");
+    assert_eq!(event.trace, "projects/my-proj/traces/abc123");
+    assert_eq!(
+        event.labels.get("service").map(String::as_str),
+        Some("checkout")
+    );
+    assert!(!event.insert_id.is_empty());
+    assert!(event.source_location.is_none());
+}
+
+#[test]
+fn builds_configured_layers() {
+    let layer = Stackdriver::builder()
+        .with_writer(std::io::sink)
+        .with_project_id("my-proj")
+        .with_labels([("service", "checkout")])
+        .with_source_location(false)
+        .with_auto_insert_id(true)
+        .with_severity_mapping(|level| match *level {
+            Level::INFO => LogSeverity::Notice,
+            _ => LogSeverity::from(level),
+        })
+        .configure(|layer| layer.with_target(false))
+        .build();
+
+    let events = layer
+        .as_json(|| tracing::info!(trace_id = "abc123", "hello!"))
+        .expect("Error converting events to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["severity"], "NOTICE");
+    assert_eq!(
+        event["logging.googleapis.com/trace"],
+        "projects/my-proj/traces/abc123"
+    );
+    assert_eq!(
+        event["logging.googleapis.com/labels"]["service"],
+        "checkout"
+    );
+    assert!(event["logging.googleapis.com/insertId"].is_string());
+    assert!(event.get("logging.googleapis.com/sourceLocation").is_none());
+    assert!(event.get("target").is_none());
+}
@@ -1,7 +1,7 @@
 #![allow(clippy::disallowed_names)]
 use std::collections::BTreeMap;
 
-use helpers::run_with_tracing;
+use helpers::{run_with_tracing, run_with_tracing_layer, run_with_tracing_warnings};
 use mocks::{MockDefaultEvent, MockEventWithSpan};
 use serde::Deserialize;
 use time::OffsetDateTime;
@@ -55,6 +55,58 @@ fn includes_custom_fields_with_dot() {
     );
 }
 
+#[test]
+fn nests_dotted_fields() {
+    let layer = tracing_stackdriver::layer().with_nested_fields(true);
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::info!(
+            db.connection.pool_size = 3,
+            db.host = "localhost",
+            db.port = 5432,
+            "message"
+        )
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(
+        event["db"],
+        serde_json::json!({
+            "connection": { "poolSize": 3 },
+            "host": "localhost",
+            "port": 5432,
+        })
+    );
+}
+
+#[test]
+fn prefers_nested_objects_in_field_collisions() {
+    let layer = tracing_stackdriver::layer().with_nested_fields(true);
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::info!(db = "primary", db.host = "localhost", "message")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["db"], serde_json::json!({ "host": "localhost" }));
+}
+
+#[test]
+fn warns_about_nested_field_collisions() {
+    let layer = tracing_stackdriver::layer().with_nested_fields(true);
+
+    let (events, warnings) = run_with_tracing_warnings::<serde_json::Value>(layer, || {
+        tracing::info!(db = "primary", db.host = "localhost", "message")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["db"], serde_json::json!({ "host": "localhost" }));
+    assert_eq!(warnings, ["field db collides with a nested object"]);
+}
+
 #[test]
 fn handles_stringly_severity_override() {
     let events = run_with_tracing::<MockDefaultEvent>(|| {
@@ -80,6 +132,11 @@ fn handles_enum_severity_override() {
     assert_eq!(event.severity, "NOTICE");
 }
 
+#[derive(Deserialize)]
+struct MockEventWithRawTime {
+    time: String,
+}
+
 #[test]
 fn includes_correct_timestamps() {
     let mut events = run_with_tracing::<MockDefaultEvent>(|| {
@@ -94,6 +151,26 @@ fn includes_correct_timestamps() {
     let first_event = events.next().expect("Error logging first event");
     let second_event = events.next().expect("Error logging second event");
     assert!(first_event.time < second_event.time);
+
+    // back-to-back events keep their order when sorted by their serialized nanosecond timestamps
+    // (which coarse clocks can repeat)
+    let events = run_with_tracing::<MockEventWithRawTime>(|| {
+        for _ in 0..100 {
+            tracing::info!("some stackdriver message");
+        }
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let times: Vec<_> = events.into_iter().map(|event| event.time).collect();
+
+    for time in &times {
+        let (_, subseconds) = time.split_once('.').expect("No subseconds in timestamp");
+        assert_eq!(subseconds.len(), "123456789Z".len(), "{time}");
+    }
+
+    for pair in times.windows(2) {
+        assert!(pair[0] <= pair[1], "{} is after {}", pair[0], pair[1]);
+    }
 }
 
 #[derive(Deserialize)]
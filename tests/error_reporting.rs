@@ -0,0 +1,327 @@
+use helpers::run_with_tracing_layer;
+use serde::Deserialize;
+use tracing_stackdriver::{LogSeverity, ServiceContext};
+
+mod helpers;
+mod mocks;
+
+static REPORTED_ERROR_EVENT_TYPE: &str =
+    "type.googleapis.com/google.devtools.clouderrorreporting.v1beta1.ReportedErrorEvent";
+
+#[derive(Debug, Deserialize)]
+struct MockServiceContext {
+    service: String,
+    version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MockErrorEvent {
+    severity: String,
+    #[serde(rename = "@type")]
+    r#type: Option<String>,
+    service_context: Option<MockServiceContext>,
+}
+
+fn service_context() -> ServiceContext {
+    ServiceContext {
+        service: "checkout".into(),
+        version: Some("1.2.3".into()),
+    }
+}
+
+#[test]
+fn marks_error_events() {
+    let layer = tracing_stackdriver::layer().with_error_reporting(service_context());
+
+    let events = run_with_tracing_layer::<MockErrorEvent>(layer, || {
+        tracing::warn!("just a warning");
+        tracing::error!("an error");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let mut events = events.into_iter();
+    let warning = events.next().expect("No warning heard");
+    let error = events.next().expect("No error heard");
+
+    assert!(warning.r#type.is_none());
+    assert!(warning.service_context.is_none());
+
+    assert_eq!(error.r#type.as_deref(), Some(REPORTED_ERROR_EVENT_TYPE));
+    let service_context = error.service_context.expect("No serviceContext found");
+    assert_eq!(service_context.service, "checkout");
+    assert_eq!(service_context.version.as_deref(), Some("1.2.3"));
+}
+
+#[test]
+fn marks_events_above_custom_thresholds() {
+    let layer = tracing_stackdriver::layer()
+        .with_error_reporting(service_context())
+        .with_error_reporting_threshold(LogSeverity::Critical);
+
+    let events = run_with_tracing_layer::<MockErrorEvent>(layer, || {
+        tracing::error!("an error");
+        tracing::error!(severity = "critical", "a critical error");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let mut events = events.into_iter();
+    let error = events.next().expect("No error heard");
+    let critical = events.next().expect("No critical error heard");
+
+    assert_eq!(error.severity, "ERROR");
+    assert!(error.r#type.is_none());
+    assert_eq!(critical.severity, "CRITICAL");
+    assert_eq!(critical.r#type.as_deref(), Some(REPORTED_ERROR_EVENT_TYPE));
+}
+
+#[test]
+fn omits_error_reporting_by_default() {
+    let events = run_with_tracing_layer::<MockErrorEvent>(tracing_stackdriver::layer(), || {
+        tracing::error!("an error")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert!(event.r#type.is_none());
+    assert!(event.service_context.is_none());
+}
+
+#[test]
+fn reports_error_events_without_service_contexts() {
+    let layer = tracing_stackdriver::layer().with_report_errors(true);
+
+    let events = run_with_tracing_layer::<MockErrorEvent>(layer, || {
+        tracing::warn!("just a warning");
+        tracing::error!("an error");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let mut events = events.into_iter();
+    let warning = events.next().expect("No warning heard");
+    let error = events.next().expect("No error heard");
+
+    assert!(warning.r#type.is_none());
+    assert_eq!(error.r#type.as_deref(), Some(REPORTED_ERROR_EVENT_TYPE));
+    assert!(error.service_context.is_none());
+}
+
+#[test]
+fn preserves_custom_types_of_reported_errors() {
+    let layer = tracing_stackdriver::layer().with_report_errors(true);
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::error!(
+            message = "an error",
+            "@type" = "type.example.com/CustomError"
+        );
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let error = events.first().expect("No error heard");
+    let error = error.as_object().expect("Error event isn't an object");
+    assert_eq!(error["@type"], "type.example.com/CustomError");
+    assert_eq!(error.keys().filter(|key| key.ends_with("type")).count(), 1);
+}
+
+#[test]
+fn includes_service_contexts_in_every_error_event() {
+    let layer = tracing_stackdriver::layer()
+        .with_error_reporting(ServiceContext {
+            service: "checkout".into(),
+            version: None,
+        })
+        .with_pretty(true);
+
+    let events = run_with_tracing_layer::<MockErrorEvent>(layer, || {
+        tracing::error!("first error");
+        tracing::error!("second error");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    assert_eq!(events.len(), 2);
+
+    for event in events {
+        let service_context = event.service_context.expect("No serviceContext found");
+        assert_eq!(service_context.service, "checkout");
+        assert!(service_context.version.is_none());
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithStackTrace {
+    stack_trace: Option<String>,
+}
+
+#[test]
+fn includes_stack_traces_above_thresholds() {
+    let layer = tracing_stackdriver::layer().with_stack_traces(Some(LogSeverity::Error));
+
+    let events = run_with_tracing_layer::<MockEventWithStackTrace>(layer, || {
+        tracing::warn!("just a warning");
+        tracing::error!("an error");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let mut events = events.into_iter();
+    let warning = events.next().expect("No warning heard");
+    let error = events.next().expect("No error heard");
+
+    assert!(warning.stack_trace.is_none());
+    let stack_trace = error.stack_trace.expect("No stack_trace found");
+    assert!(stack_trace.contains("includes_stack_traces_above_thresholds"));
+}
+
+#[test]
+fn excludes_stack_traces_by_default() {
+    let events =
+        run_with_tracing_layer::<MockEventWithStackTrace>(tracing_stackdriver::layer(), || {
+            tracing::error!("an error")
+        })
+        .expect("Error converting test buffer to JSON");
+
+    let error = events.first().expect("No error heard");
+    assert!(error.stack_trace.is_none());
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MockReportLocation {
+    file_path: String,
+    line_number: u32,
+    function_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MockErrorContext {
+    report_location: MockReportLocation,
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithErrorContext {
+    context: Option<MockErrorContext>,
+}
+
+#[test]
+fn includes_report_locations_in_error_events() {
+    let layer = tracing_stackdriver::layer().with_report_errors(true);
+
+    let events = run_with_tracing_layer::<MockEventWithErrorContext>(layer, || {
+        tracing::warn!("just a warning");
+        tracing::error!("an error");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let mut events = events.into_iter();
+    let warning = events.next().expect("No warning heard");
+    let error = events.next().expect("No error heard");
+
+    assert!(warning.context.is_none());
+    let report_location = error.context.expect("No context found").report_location;
+    assert_eq!(report_location.file_path, file!());
+    assert!(report_location.line_number > 0);
+    assert_eq!(report_location.function_name, module_path!());
+}
+
+#[test]
+fn omits_report_locations_by_default() {
+    let events =
+        run_with_tracing_layer::<MockEventWithErrorContext>(tracing_stackdriver::layer(), || {
+            tracing::error!("an error")
+        })
+        .expect("Error converting test buffer to JSON");
+
+    let error = events.first().expect("No error heard");
+    assert!(error.context.is_none());
+}
+
+#[test]
+fn preserves_stack_trace_fields_of_events() {
+    let layer = tracing_stackdriver::layer().with_stack_traces(Some(LogSeverity::Error));
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::error!(stack_trace = "custom trace", "an error");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let error = events.first().expect("No error heard");
+    let error = error.as_object().expect("Error event isn't an object");
+    assert_eq!(error["stack_trace"], "custom trace");
+    assert!(!error.contains_key("stackTrace"));
+}
+
+#[test]
+fn includes_http_requests_in_error_contexts() {
+    let layer = tracing_stackdriver::layer().with_report_errors(true);
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::error!(
+            http_request.request_method = "POST",
+            http_request.request_url = "https://example.com/checkout",
+            http_request.status = 500,
+            http_request.latency = "0.23s",
+            "checkout failed"
+        );
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let error = events.first().expect("No error heard");
+    assert_eq!(
+        error["context"]["httpRequest"],
+        serde_json::json!({
+            "method": "POST",
+            "url": "https://example.com/checkout",
+            "responseStatusCode": 500,
+        })
+    );
+    assert_eq!(error["httpRequest"]["latency"], "0.23s");
+}
+
+#[test]
+fn omits_http_requests_from_error_contexts_without_requests() {
+    let layer = tracing_stackdriver::layer().with_report_errors(true);
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::error!("an error");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let error = events.first().expect("No error heard");
+    assert!(error["context"].get("httpRequest").is_none());
+}
+
+#[test]
+fn reports_events_matching_custom_filters() {
+    let layer = tracing_stackdriver::layer()
+        .with_report_errors(true)
+        .with_error_reporting_filter(|event, severity| {
+            let noisy = event.metadata().target() == "noisy";
+            let has_error = event.metadata().fields().field("error").is_some();
+
+            !noisy && (has_error || severity >= LogSeverity::Error)
+        });
+
+    let events = run_with_tracing_layer::<MockErrorEvent>(layer, || {
+        tracing::warn!(error = "connection reset", "handled failure");
+        tracing::warn!("just a warning");
+        tracing::error!(target: "noisy", "noisy error");
+        tracing::error!("an error");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let reported: Vec<_> = events
+        .iter()
+        .map(|event| (event.severity.as_str(), event.r#type.is_some()))
+        .collect();
+
+    assert_eq!(
+        reported,
+        [
+            ("WARNING", true),
+            ("WARNING", false),
+            ("ERROR", false),
+            ("ERROR", true)
+        ]
+    );
+}
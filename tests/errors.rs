@@ -0,0 +1,241 @@
+use helpers::{run_with_tracing, run_with_tracing_layer};
+use serde::Deserialize;
+use std::fmt;
+
+mod helpers;
+mod mocks;
+
+#[derive(Debug)]
+struct MockError {
+    message: &'static str,
+    source: Option<Box<MockError>>,
+}
+
+impl MockError {
+    fn chain(messages: &[&'static str]) -> Self {
+        messages
+            .iter()
+            .rev()
+            .fold(None, |source, message| {
+                Some(MockError {
+                    message,
+                    source: source.map(Box::new),
+                })
+            })
+            .expect("Empty error chain")
+    }
+}
+
+impl fmt::Display for MockError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(self.message)
+    }
+}
+
+impl std::error::Error for MockError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn std::error::Error + 'static))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MockErrorField {
+    message: String,
+    causes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithError {
+    error: MockErrorField,
+}
+
+#[test]
+fn includes_error_source_chains() {
+    let error = MockError::chain(&["request failed", "connection refused"]);
+
+    let events = run_with_tracing::<MockEventWithError>(|| {
+        tracing::error!(error = &error as &dyn std::error::Error, "failed")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.error.message, "request failed: connection refused");
+    assert_eq!(event.error.causes, vec!["connection refused"]);
+}
+
+#[test]
+fn caps_error_source_chains() {
+    let error = MockError::chain(&["first", "second", "third", "fourth"]);
+    let layer = tracing_stackdriver::layer().with_error_chain_depth(2);
+
+    let events = run_with_tracing_layer::<MockEventWithError>(layer, || {
+        tracing::error!(error = &error as &dyn std::error::Error, "failed")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.error.message, "first: second: third");
+    assert_eq!(event.error.causes, vec!["second", "third"]);
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithMultiLineError {
+    error: String,
+}
+
+#[test]
+fn formats_error_source_chains_as_multi_line_strings() {
+    let error = MockError::chain(&["request failed", "connection refused", "timed out"]);
+    let layer =
+        tracing_stackdriver::layer().with_error_format(tracing_stackdriver::ErrorFormat::MultiLine);
+
+    let events = run_with_tracing_layer::<MockEventWithMultiLineError>(layer, || {
+        tracing::error!(error = &error as &dyn std::error::Error, "failed")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(
+        event.error,
+        "request failed\ncaused by: connection refused\ncaused by: timed out"
+    );
+}
+
+/// Error that is its own source
+#[derive(Debug)]
+struct CyclicError;
+
+impl fmt::Display for CyclicError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("cyclic")
+    }
+}
+
+impl std::error::Error for CyclicError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&CyclicError)
+    }
+}
+
+#[test]
+fn truncates_cyclic_error_source_chains() {
+    let events = run_with_tracing::<MockEventWithError>(|| {
+        tracing::error!(error = &CyclicError as &dyn std::error::Error, "failed")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.error.causes.len(), 10);
+}
+
+#[test]
+fn combines_error_messages_with_stack_traces() {
+    let error = MockError::chain(&["request failed", "connection refused"]);
+    let layer = tracing_stackdriver::layer()
+        .with_report_errors(true)
+        .with_stack_traces(Some(tracing_stackdriver::LogSeverity::Error))
+        .with_combined_error_messages(true);
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::warn!(error = &error as &dyn std::error::Error, "warning");
+        tracing::error!("no error field");
+        tracing::error!(error = &error as &dyn std::error::Error, "failed");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    assert_eq!(events[0]["message"], "warning");
+    assert_eq!(events[1]["message"], "no error field");
+
+    let stack_trace = events[2]["stack_trace"]
+        .as_str()
+        .expect("No stack_trace found");
+    assert_eq!(
+        events[2]["message"],
+        format!("request failed: connection refused\n{stack_trace}")
+    );
+    assert_eq!(
+        events[2]["error"]["message"],
+        "request failed: connection refused"
+    );
+}
+
+#[test]
+fn truncates_combined_error_messages() {
+    let error = MockError::chain(&["request failed", "connection refused"]);
+    let layer = tracing_stackdriver::layer()
+        .with_report_errors(true)
+        .with_combined_error_messages(true)
+        .with_max_field_bytes(Some(24));
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::error!(
+            error = &error as &dyn std::error::Error,
+            stack_trace = "at main.rs:1",
+            "failed"
+        );
+    })
+    .expect("Error converting test buffer to JSON");
+
+    assert_eq!(events[0]["message"], "request failed: conne…");
+    assert_eq!(events[0]["messageTruncated"], true);
+    assert_eq!(events[0]["stack_trace"], "at main.rs:1");
+}
+
+#[test]
+fn keeps_error_messages_by_default() {
+    let error = MockError::chain(&["request failed"]);
+    let layer = tracing_stackdriver::layer()
+        .with_report_errors(true)
+        .with_stack_traces(Some(tracing_stackdriver::LogSeverity::Error));
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::error!(error = &error as &dyn std::error::Error, "failed");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    assert_eq!(events[0]["message"], "failed");
+}
+
+#[tracing::instrument(err)]
+fn failing_operation() -> Result<(), MockError> {
+    Err(MockError::chain(&["operation failed"]))
+}
+
+#[test]
+fn keeps_error_strings_of_other_events() {
+    let events = run_with_tracing::<serde_json::Value>(|| {
+        tracing::warn!(error = "timeout");
+        tracing::info!(error = "none");
+        tracing::error!(error = "timeout", attempt = 3);
+        tracing::error!(error = "timeout", "retrying");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let errors: Vec<_> = events.iter().map(|event| &event["error"]).collect();
+    assert_eq!(errors, ["timeout", "none", "timeout", "timeout"]);
+}
+
+#[test]
+fn structures_instrumented_errors() {
+    let layer = tracing_stackdriver::layer().with_report_errors(true);
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        let _ = failing_operation();
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["severity"], "ERROR");
+    assert_eq!(
+        event["@type"],
+        "type.googleapis.com/google.devtools.clouderrorreporting.v1beta1.ReportedErrorEvent"
+    );
+    assert_eq!(event["error"]["message"], "operation failed");
+    assert_eq!(event["error"]["causes"], serde_json::json!([]));
+    assert_eq!(
+        event["logging.googleapis.com/sourceLocation"]["file"],
+        "tests/errors.rs"
+    );
+}
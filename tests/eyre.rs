@@ -0,0 +1,67 @@
+#![cfg(feature = "eyre")]
+use eyre::WrapErr;
+use serde::Deserialize;
+use tracing_stackdriver::report_value;
+
+#[derive(Debug, Deserialize)]
+struct MockError {
+    message: String,
+    causes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithError {
+    error: MockError,
+}
+
+fn load_config() -> eyre::Result<()> {
+    Err(eyre::eyre!("file not found"))
+        .wrap_err("couldn't read config.toml")
+        .wrap_err("couldn't load config")
+}
+
+#[test]
+fn records_eyre_report_chains_in_order() {
+    let report = load_config().expect_err("Config unexpectedly loaded");
+
+    let events = tracing_stackdriver::layer()
+        .as_json(|| {
+            tracing::error!(
+                error = &report_value(&report) as &dyn std::error::Error,
+                "startup failed"
+            )
+        })
+        .expect("Error converting events to JSON");
+
+    let event: MockEventWithError =
+        serde_json::from_value(events[0].clone()).expect("Error deserializing event");
+
+    let chain: Vec<_> = report.chain().map(ToString::to_string).collect();
+    assert_eq!(event.error.message, chain.join(": "));
+    assert_eq!(event.error.causes, chain[1..]);
+}
+
+#[cfg(all(tracing_unstable, feature = "valuable"))]
+#[test]
+fn records_eyre_reports_as_structured_values() {
+    use valuable::Valuable;
+
+    let report = load_config().expect_err("Config unexpectedly loaded");
+    let error_value = report_value(&report);
+
+    let events = tracing_stackdriver::layer()
+        .as_json(|| tracing::error!(error = error_value.as_value(), "startup failed"))
+        .expect("Error converting events to JSON");
+
+    let event: MockEventWithError =
+        serde_json::from_value(events[0].clone()).expect("Error deserializing event");
+
+    assert_eq!(
+        event.error.message,
+        "couldn't load config: couldn't read config.toml: file not found"
+    );
+    assert_eq!(
+        event.error.causes,
+        ["couldn't read config.toml", "file not found"]
+    );
+}
@@ -0,0 +1,102 @@
+use helpers::run_with_tracing_layer;
+
+mod helpers;
+
+#[test]
+fn includes_only_allowed_fields() {
+    let layer = tracing_stackdriver::layer().with_included_fields(["user_id"]);
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        let span = tracing::info_span!("request", user_id = 42, session = "abc");
+        let _span = span.enter();
+        tracing::info!(user_id = 42, email = "user@example.com", "logged in");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["userId"], 42);
+    assert!(event.get("email").is_none());
+    assert_eq!(event["message"], "logged in");
+    assert_eq!(event["severity"], "INFO");
+    assert!(event.get("time").is_some());
+    assert_eq!(
+        event["span"],
+        serde_json::json!({ "name": "request", "user_id": 42 })
+    );
+}
+
+#[test]
+fn excludes_denied_fields() {
+    let layer = tracing_stackdriver::layer()
+        .with_included_fields(["user_id", "email"])
+        .with_excluded_fields(["email"]);
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::info!(user_id = 42, email = "user@example.com", "logged in");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["userId"], 42);
+    assert!(event.get("email").is_none());
+}
+
+#[test]
+fn filters_fields_by_prefix() {
+    let layer = tracing_stackdriver::layer()
+        .with_excluded_fields(["user.*"])
+        .with_flattened_spans(true);
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        let span = tracing::info_span!("request", user.name = "alice", users = 3);
+        let _span = span.enter();
+        tracing::info!(user.email = "user@example.com", "logged in");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert!(event.get("userEmail").is_none());
+    assert!(event.get("userName").is_none());
+    assert_eq!(event["users"], 3);
+}
+
+#[test]
+fn redacts_field_values() {
+    let layer = tracing_stackdriver::layer().with_redacted_fields(["password", "auth.*"]);
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        let span = tracing::info_span!("login", password = "hunter2");
+        let _span = span.enter();
+        tracing::info!(
+            password = "hunter2",
+            auth.token = "secret",
+            user_id = 42,
+            "logged in"
+        );
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["password"], "[REDACTED]");
+    assert_eq!(event["authToken"], "[REDACTED]");
+    assert_eq!(event["userId"], 42);
+    assert_eq!(event["span"]["password"], "[REDACTED]");
+}
+
+#[test]
+fn redacts_nested_field_values() {
+    let layer = tracing_stackdriver::layer()
+        .with_nested_fields(true)
+        .with_redacted_fields(["auth.*"]);
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::info!(auth.token = "secret", auth.user = "alice", "logged in");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(
+        event["auth"],
+        serde_json::json!({ "token": "[REDACTED]", "user": "[REDACTED]" })
+    );
+}
@@ -43,6 +43,47 @@ where
         .collect()
 }
 
+/// Run a traced callback against a Layer configuration, returning both its events and the
+/// warnings passed to its error handler
+pub fn run_with_tracing_warnings<E>(
+    layer: Layer<Registry>,
+    callback: impl FnOnce(),
+) -> serde_json::Result<(Vec<E>, Vec<String>)>
+where
+    E: for<'a> Deserialize<'a>,
+{
+    let buffer = Arc::new(Mutex::new(vec![]));
+    let shared = buffer.clone();
+    let warnings = Arc::new(Mutex::new(vec![]));
+    let shared_warnings = warnings.clone();
+    let stackdriver = layer
+        .with_writer(move || MockWriter(shared.clone()))
+        .on_error(move |error| {
+            shared_warnings
+                .lock()
+                .expect("Couldn't get lock on test warnings")
+                .push(error.to_string())
+        });
+    let subscriber = Registry::default().with(stackdriver);
+
+    tracing::subscriber::with_default(subscriber, callback);
+
+    let buffer = buffer
+        .lock()
+        .expect("Couldn't get lock on test write target");
+
+    let events = serde_json::Deserializer::from_slice(&buffer)
+        .into_iter()
+        .collect::<serde_json::Result<_>>()?;
+
+    let warnings = warnings
+        .lock()
+        .expect("Couldn't get lock on test warnings")
+        .clone();
+
+    Ok((events, warnings))
+}
+
 // FIXME: make this entirely internal
 #[derive(Debug)]
 pub struct MockWriter(pub Arc<Mutex<Vec<u8>>>);
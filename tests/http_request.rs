@@ -1,4 +1,4 @@
-use helpers::run_with_tracing;
+use helpers::{run_with_tracing, run_with_tracing_layer, run_with_tracing_warnings};
 use mocks::{MockHttpEvent, MockHttpRequest};
 
 mod helpers;
@@ -32,3 +32,117 @@ fn nests_http_request() {
     let event = events.first().expect("No event heard");
     assert_eq!(event.http_request, mock_http_request);
 }
+
+#[test]
+fn keeps_validated_latency_strings() {
+    let events = run_with_tracing_layer::<serde_json::Value>(
+        tracing_stackdriver::layer().with_latency_validation(true),
+        || {
+            tracing::info!(http_request.latency = "0.23s", "valid latency");
+            tracing::info!(http_request.latency = "230ms", "invalid latency");
+        },
+    )
+    .expect("Error converting test buffer to JSON");
+
+    let latencies: Vec<_> = events
+        .iter()
+        .map(|event| event["httpRequest"]["latency"].as_str())
+        .collect();
+
+    assert_eq!(latencies, [Some("0.23s"), Some("230ms")]);
+}
+
+#[test]
+fn warns_about_invalid_latency_strings() {
+    let (_, warnings) = run_with_tracing_warnings::<serde_json::Value>(
+        tracing_stackdriver::layer().with_latency_validation(true),
+        || {
+            tracing::info!(http_request.latency = "0.23s", "valid latency");
+            tracing::info!(http_request.latency = "230ms", "invalid latency");
+        },
+    )
+    .expect("Error converting test buffer to JSON");
+
+    assert_eq!(
+        warnings,
+        ["invalid http_request.latency \"230ms\", expected a duration like \"0.23s\""]
+    );
+}
+
+#[test]
+fn omits_latency_warnings_without_validation() {
+    let (_, warnings) =
+        run_with_tracing_warnings::<serde_json::Value>(tracing_stackdriver::layer(), || {
+            tracing::info!(http_request.latency = "230ms", "invalid latency");
+        })
+        .expect("Error converting test buffer to JSON");
+
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn converts_numeric_latencies_to_durations() {
+    let events = run_with_tracing_layer::<serde_json::Value>(tracing_stackdriver::layer(), || {
+        tracing::info!(http_request.latency_ms = 1234u64, "milliseconds");
+        tracing::info!(http_request.latency_us = 1500u64, "microseconds");
+        tracing::info!(
+            http_request.latency = "0.5s",
+            http_request.latency_ms = 1234u64,
+            "explicit latency"
+        );
+        tracing::info!(http_request.latency_ms = 1.5f64, "fractional milliseconds");
+        tracing::info!(http_request.latency_us = 2.5f64, "fractional microseconds");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    assert_eq!(
+        events[0]["httpRequest"],
+        serde_json::json!({ "latency": "1.234000000s" })
+    );
+    assert_eq!(
+        events[1]["httpRequest"],
+        serde_json::json!({ "latency": "0.001500000s" })
+    );
+    assert_eq!(
+        events[2]["httpRequest"],
+        serde_json::json!({ "latency": "0.5s" })
+    );
+    assert_eq!(
+        events[3]["httpRequest"],
+        serde_json::json!({ "latency": "0.001500000s" })
+    );
+    assert_eq!(
+        events[4]["httpRequest"],
+        serde_json::json!({ "latency": "0.000002500s" })
+    );
+}
+
+#[test]
+fn drops_invalid_numeric_latencies() {
+    let (events, warnings) =
+        run_with_tracing_warnings::<serde_json::Value>(tracing_stackdriver::layer(), || {
+            tracing::info!(http_request.latency_ms = "fast", "non-numeric latency");
+            tracing::info!(http_request.latency_us = -5i64, "negative latency");
+            tracing::info!(
+                http_request.latency_ms = -1.5f64,
+                http_request.status = 200,
+                "negative fractional latency"
+            );
+        })
+        .expect("Error converting test buffer to JSON");
+
+    assert!(events[0].get("httpRequest").is_none());
+    assert!(events[1].get("httpRequest").is_none());
+    assert_eq!(
+        events[2]["httpRequest"],
+        serde_json::json!({ "status": 200 })
+    );
+    assert_eq!(
+        warnings,
+        [
+            "invalid http_request.latency_ms \"fast\", expected a non-negative number",
+            "invalid http_request.latency_us -5, expected a non-negative number",
+            "invalid http_request.latency_ms -1.5, expected a non-negative number",
+        ]
+    );
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "http-request")]
+use helpers::run_with_tracing;
+use tracing::Level;
+use tracing_stackdriver::HttpRequest;
+
+mod helpers;
+
+#[test]
+fn records_http_requests_as_dotted_fields() {
+    let http_request = HttpRequest::builder()
+        .request_method(http::Method::POST)
+        .request_url("https://example.com/orders".parse().unwrap())
+        .request_size(42)
+        .status(http::StatusCode::CREATED)
+        .remote_ip(std::net::IpAddr::from([127, 0, 0, 1]))
+        .latency(std::time::Duration::from_millis(25))
+        .cache_hit(false)
+        .build();
+
+    let events = run_with_tracing::<serde_json::Value>(|| {
+        tracing_stackdriver::http_request_event!(
+            Level::INFO,
+            http_request,
+            user_id = 42,
+            "Request handled"
+        )
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(
+        event["httpRequest"],
+        serde_json::json!({
+            "requestMethod": "POST",
+            "requestUrl": "https://example.com/orders",
+            "requestSize": "42",
+            "status": 201,
+            "remoteIp": "127.0.0.1",
+            "latency": "0.025000000s",
+            "cacheHit": false,
+        })
+    );
+    assert_eq!(event["userId"], 42);
+    assert_eq!(event["message"], "Request handled");
+}
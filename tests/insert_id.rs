@@ -1,4 +1,4 @@
-use helpers::run_with_tracing;
+use helpers::{run_with_tracing, run_with_tracing_layer};
 use mocks::MockDefaultEvent;
 
 mod helpers;
@@ -36,3 +36,49 @@ fn omits_insert_id_by_default() {
     let event = events.first().expect("No event heard");
     assert!(event.insert_id.is_none());
 }
+
+#[test]
+fn generates_ordered_insert_ids() {
+    let layer = tracing_stackdriver::layer().with_auto_insert_id(true);
+
+    let events = run_with_tracing_layer::<MockDefaultEvent>(layer, || {
+        tracing::info!("hello!");
+        tracing::info!("hello again!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let mut events = events.into_iter();
+    let first = events.next().and_then(|event| event.insert_id);
+    let second = events.next().and_then(|event| event.insert_id);
+
+    assert!(first.is_some() && second.is_some());
+    assert!(first < second, "{first:?} should precede {second:?}");
+}
+
+#[test]
+fn prefers_custom_insert_ids_over_generated_ones() {
+    let layer = tracing_stackdriver::layer().with_auto_insert_id(true);
+
+    let events = run_with_tracing_layer::<MockDefaultEvent>(layer, || {
+        tracing::info!(insert_id = "my-new-event", "hello!")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.insert_id.as_deref(), Some("my-new-event"));
+}
+
+#[test]
+fn generates_custom_insert_ids() {
+    let layer = tracing_stackdriver::layer()
+        .with_insert_id_generator(|event| format!("{}-1234", event.metadata().target()));
+
+    let events = run_with_tracing_layer::<MockDefaultEvent>(
+        layer,
+        || tracing::info!(target: "requests", "hello!"),
+    )
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.insert_id.as_deref(), Some("requests-1234"));
+}
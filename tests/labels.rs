@@ -1,4 +1,4 @@
-use helpers::run_with_tracing;
+use helpers::{run_with_tracing, run_with_tracing_layer};
 use mocks::MockDefaultEvent;
 use std::collections::BTreeMap;
 
@@ -21,9 +21,9 @@ fn nests_labels() {
     .expect("Error converting test buffer to JSON");
 
     let event = events.first().expect("No event heard");
-    assert!(event.labels.get("foo").is_some());
+    assert!(event.labels.contains_key("foo"));
     assert_eq!(event.labels.get("foo"), labels.get("foo"));
-    assert!(event.labels.get("baz").is_some());
+    assert!(event.labels.contains_key("baz"));
     assert_eq!(event.labels.get("baz"), labels.get("baz"));
 }
 
@@ -56,3 +56,66 @@ fn omits_labels_by_default() {
     let event = events.first().expect("No event heard");
     assert!(event.labels.is_empty());
 }
+
+#[test]
+fn includes_static_labels() {
+    let layer = tracing_stackdriver::layer().with_labels([("service", "billing")]);
+
+    let events = run_with_tracing_layer::<MockDefaultEvent>(layer, || tracing::info!("hello!"))
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.labels.get("service"), Some(&"billing".to_string()));
+}
+
+#[test]
+fn merges_static_and_event_labels() {
+    let layer =
+        tracing_stackdriver::layer().with_labels([("service", "billing"), ("replicas", "3")]);
+
+    let events = run_with_tracing_layer::<MockDefaultEvent>(layer, || {
+        tracing::info!(labels.tenant = "acme", "hello!")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.labels.get("service"), Some(&"billing".to_string()));
+    assert_eq!(event.labels.get("replicas"), Some(&"3".to_string()));
+    assert_eq!(event.labels.get("tenant"), Some(&"acme".to_string()));
+}
+
+#[test]
+fn inherits_span_labels() {
+    let events = run_with_tracing::<MockDefaultEvent>(|| {
+        let span = tracing::info_span!("request", labels.tenant = "acme", labels.region = "eu");
+        let _guard = span.enter();
+        tracing::info!(labels.region = "us", "hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.labels.get("tenant"), Some(&"acme".to_string()));
+    assert_eq!(event.labels.get("region"), Some(&"us".to_string()));
+}
+
+#[test]
+fn prefers_event_labels_over_static_labels() {
+    let static_labels: std::collections::HashMap<String, String> = [
+        ("service".to_string(), "checkout".to_string()),
+        ("region".to_string(), "us-east1".to_string()),
+    ]
+    .into();
+    let layer = tracing_stackdriver::layer().with_labels(static_labels);
+
+    let events = run_with_tracing_layer::<MockDefaultEvent>(layer, || {
+        tracing::info!(labels.region = "europe-west1", "hello!")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.labels.get("service"), Some(&"checkout".to_string()));
+    assert_eq!(
+        event.labels.get("region"),
+        Some(&"europe-west1".to_string())
+    );
+}
@@ -0,0 +1,33 @@
+use helpers::run_with_tracing;
+
+mod helpers;
+
+#[test]
+fn formats_forwarded_log_records_like_events() {
+    tracing_log::LogTracer::init().expect("Could not forward log records");
+
+    let events = run_with_tracing::<serde_json::Map<String, serde_json::Value>>(|| {
+        log::info!(target: "checkout", "hello!");
+        tracing::info!(target: "checkout", "hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let [log_event, tracing_event] = &events[..] else {
+        panic!("Expected two events, heard {}", events.len());
+    };
+
+    assert_eq!(log_event["target"], "checkout");
+    assert_eq!(log_event["severity"], "INFO");
+    assert_eq!(log_event["message"], "hello!");
+    for key in ["logTarget", "logModulePath", "logFile", "logLine"] {
+        assert!(!log_event.contains_key(key), "{key} found in {log_event:?}");
+    }
+
+    let keys = |event: &serde_json::Map<_, _>| event.keys().cloned().collect::<Vec<String>>();
+    assert_eq!(keys(log_event), keys(tracing_event));
+
+    let source_location = &log_event["logging.googleapis.com/sourceLocation"];
+    assert_eq!(source_location["file"], "tests/log.rs");
+    assert_eq!(source_location["line"], "10");
+    assert_eq!(source_location["function"], "log");
+}
@@ -0,0 +1,36 @@
+use helpers::run_with_tracing_layer;
+
+mod helpers;
+
+type MockEvent = serde_json::Map<String, serde_json::Value>;
+
+#[test]
+fn maps_targets_to_log_names() {
+    let layer = tracing_stackdriver::layer().with_log_name_mapper(|metadata| {
+        (metadata.target() == "audit").then(|| "audit-log".to_owned())
+    });
+
+    let events = run_with_tracing_layer::<MockEvent>(layer, || {
+        tracing::info!(target: "audit", "user signed in");
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let mut events = events.into_iter();
+    let audit = events.next().expect("No audit event heard");
+    let application = events.next().expect("No application event heard");
+    assert_eq!(audit["logging.googleapis.com/logName"], "audit-log");
+    assert!(!application.contains_key("logging.googleapis.com/logName"));
+}
+
+#[test]
+fn omits_log_names_by_default() {
+    let events = run_with_tracing_layer::<MockEvent>(
+        tracing_stackdriver::layer(),
+        || tracing::info!(target: "audit", "user signed in"),
+    )
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert!(!event.contains_key("logging.googleapis.com/logName"));
+}
@@ -0,0 +1,207 @@
+use helpers::{run_with_tracing_layer, run_with_tracing_warnings};
+
+mod helpers;
+
+type MockEvent = serde_json::Map<String, serde_json::Value>;
+
+#[test]
+fn writes_messages_to_custom_keys() {
+    let layer = tracing_stackdriver::layer().with_message_field("msg");
+
+    let events = run_with_tracing_layer::<MockEvent>(layer, || tracing::info!("hello!"))
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["msg"], "hello!");
+    assert!(!event.contains_key("message"));
+}
+
+#[test]
+fn prefers_event_fields_over_custom_message_keys() {
+    let layer = tracing_stackdriver::layer().with_message_field("msg");
+
+    let events =
+        run_with_tracing_layer::<MockEvent>(layer, || tracing::info!(msg = "mine", "hello!"))
+            .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["msg"], "mine");
+    assert_eq!(event["message"], "hello!");
+}
+
+#[test]
+fn prefers_camel_cased_event_fields_over_custom_message_keys() {
+    let layer = tracing_stackdriver::layer().with_message_field("fooBar");
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::info!(foo_bar = 1, "hello!")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["fooBar"], 1);
+    assert_eq!(event["message"], "hello!");
+    assert!(event.get("fooBarValue").is_none());
+}
+
+#[test]
+fn keeps_messages_out_of_reserved_keys() {
+    let layer = tracing_stackdriver::layer().with_message_field("severity");
+
+    let (events, warnings) =
+        run_with_tracing_warnings::<serde_json::Value>(layer, || tracing::warn!("hello!"))
+            .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["severity"], "WARNING");
+    assert_eq!(event["message"], "hello!");
+    assert_eq!(
+        warnings,
+        ["message field severity is written by the layer itself, writing the message to message \
+          instead"]
+    );
+}
+
+#[test]
+fn truncates_oversized_messages_and_fields() {
+    let layer = tracing_stackdriver::layer().with_max_field_bytes(Some(8));
+    let message = "é".repeat(100);
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::info!(body = "0123456789", short = "ok", "{}", message)
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["message"], "éé…");
+    assert_eq!(event["messageTruncated"], true);
+    assert_eq!(event["body"], "01234…");
+    assert_eq!(event["bodyTruncated"], true);
+    assert_eq!(event["short"], "ok");
+    assert!(event.get("shortTruncated").is_none());
+}
+
+#[test]
+fn never_truncates_special_fields() {
+    let layer = tracing_stackdriver::layer()
+        .with_project_id("my-project")
+        .with_max_field_bytes(Some(8));
+    let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::info!(
+            trace_id,
+            span_id = "00f067aa0ba902b7",
+            insert_id = "0123456789abcdef",
+            labels.user = "0123456789",
+            body = "0123456789",
+            "hello!"
+        )
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(
+        event["logging.googleapis.com/trace"],
+        format!("projects/my-project/traces/{trace_id}")
+    );
+    assert_eq!(event["logging.googleapis.com/spanId"], "00f067aa0ba902b7");
+    assert_eq!(event["logging.googleapis.com/insertId"], "0123456789abcdef");
+    assert_eq!(event["logging.googleapis.com/labels"]["user"], "0123456789");
+    assert_eq!(event["body"], "01234…");
+    assert_eq!(event["bodyTruncated"], true);
+
+    let flags: Vec<_> = event
+        .as_object()
+        .expect("Event isn't an object")
+        .keys()
+        .filter(|key| key.ends_with("Truncated"))
+        .collect();
+    assert_eq!(flags, ["bodyTruncated"]);
+}
+
+#[test]
+fn truncates_within_the_byte_limit_on_character_boundaries() {
+    for max_field_bytes in 0..12 {
+        let layer = tracing_stackdriver::layer().with_max_field_bytes(Some(max_field_bytes));
+
+        let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+            tracing::info!("{}", "aé€😀".repeat(4))
+        })
+        .expect("Error converting test buffer to JSON");
+
+        let message = events[0]["message"].as_str().expect("No message found");
+        assert!(message.len() <= max_field_bytes, "{message:?} is too long");
+        assert_eq!(events[0]["messageTruncated"], true);
+    }
+}
+
+fn serialize_control_chars(
+    control_char_handling: tracing_stackdriver::ControlCharHandling,
+) -> serde_json::Value {
+    let layer = tracing_stackdriver::layer().with_control_char_handling(control_char_handling);
+
+    let mut events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::info!(body = "nul\0back\u{8}space", "line\nbreak\tand\u{1b}escape")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    events.remove(0)
+}
+
+#[test]
+fn escapes_control_chars_by_default() {
+    let event = serialize_control_chars(tracing_stackdriver::ControlCharHandling::default());
+
+    assert_eq!(event["body"], "nul\0back\u{8}space");
+    assert_eq!(event["message"], "line\nbreak\tand\u{1b}escape");
+}
+
+#[test]
+fn strips_control_chars() {
+    let event = serialize_control_chars(tracing_stackdriver::ControlCharHandling::Strip);
+
+    assert_eq!(event["body"], "nulbackspace");
+    assert_eq!(event["message"], "line\nbreak\tandescape");
+}
+
+#[test]
+fn replaces_control_chars() {
+    let event = serialize_control_chars(tracing_stackdriver::ControlCharHandling::Replace(
+        '\u{FFFD}',
+    ));
+
+    assert_eq!(event["body"], "nul\u{FFFD}back\u{FFFD}space");
+    assert_eq!(event["message"], "line\nbreak\tand\u{FFFD}escape");
+}
+
+#[test]
+fn keeps_empty_messages_by_default() {
+    let events = run_with_tracing_layer::<MockEvent>(tracing_stackdriver::layer(), || {
+        tracing::info!(count = 1, "")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["message"], "");
+}
+
+#[test]
+fn omits_empty_messages() {
+    let layer = tracing_stackdriver::layer()
+        .with_empty_messages(false)
+        .with_message_field("msg");
+
+    let events = run_with_tracing_layer::<MockEvent>(layer, || {
+        tracing::info!(count = 1, "");
+        tracing::info!(count = 2);
+        tracing::info!(count = 3, "counted");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    assert!(!events[0].contains_key("msg"));
+    assert!(!events[0].contains_key("message"));
+    assert_eq!(events[0]["count"], 1);
+    assert!(!events[1].contains_key("msg"));
+    assert_eq!(events[2]["msg"], "counted");
+}
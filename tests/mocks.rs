@@ -1,3 +1,4 @@
+#![allow(dead_code)]
 use serde::Deserialize;
 use std::collections::BTreeMap;
 use time::OffsetDateTime;
@@ -6,6 +7,7 @@ use time::OffsetDateTime;
 pub struct MockSourceLocation {
     pub file: String,
     pub line: String,
+    pub function: Option<String>,
 }
 
 #[derive(Clone, Deserialize, Debug)]
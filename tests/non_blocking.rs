@@ -0,0 +1,71 @@
+#![cfg(feature = "non-blocking")]
+use helpers::MockWriter;
+use std::sync::{Arc, Mutex};
+use time::OffsetDateTime;
+use tracing_stackdriver::Clock;
+use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+mod helpers;
+
+struct FixedClock;
+
+impl Clock for FixedClock {
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::UNIX_EPOCH
+    }
+}
+
+fn log_events() {
+    tracing::info!(answer = 42, "hello");
+
+    tracing::info_span!("request", labels.request_id = "abc").in_scope(|| {
+        tracing::warn!(http_request.status = 500_u16, "goodbye");
+    });
+}
+
+#[test]
+fn writes_identical_output_through_non_blocking_writers() {
+    let output = Arc::new(Mutex::new(vec![]));
+    let shared_output = output.clone();
+    let stackdriver = tracing_stackdriver::layer()
+        .with_clock(FixedClock)
+        .with_writer(move || MockWriter(shared_output.clone()));
+
+    tracing::subscriber::with_default(Registry::default().with(stackdriver), log_events);
+
+    let non_blocking_output = Arc::new(Mutex::new(vec![]));
+    let (make_writer, guard) =
+        tracing_appender::non_blocking(MockWriter(non_blocking_output.clone()));
+    let stackdriver = tracing_stackdriver::layer()
+        .with_clock(FixedClock)
+        .with_writer(make_writer);
+
+    tracing::subscriber::with_default(Registry::default().with(stackdriver), log_events);
+
+    // dropping the guard drains any buffered events
+    drop(guard);
+
+    let output = output
+        .lock()
+        .expect("Couldn't get lock on test write target");
+    let non_blocking_output = non_blocking_output
+        .lock()
+        .expect("Couldn't get lock on test write target");
+
+    assert!(!output.is_empty());
+    assert_eq!(
+        String::from_utf8_lossy(&non_blocking_output),
+        String::from_utf8_lossy(&output)
+    );
+}
+
+#[test]
+fn builds_non_blocking_stdout_layers() {
+    let (stackdriver, guard) = tracing_stackdriver::layer().non_blocking();
+
+    tracing::subscriber::with_default(Registry::default().with(stackdriver), || {
+        tracing::info!("hello from a worker thread")
+    });
+
+    drop(guard);
+}
@@ -3,7 +3,9 @@ use helpers::MockWriter;
 use lazy_static::lazy_static;
 use opentelemetry::{
     sdk::{testing::trace::TestSpan, trace::Tracer},
-    trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState},
+    trace::{
+        SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState, TracerProvider,
+    },
 };
 use rand::Rng;
 use serde::{de::Error, Deserialize, Deserializer};
@@ -25,9 +27,9 @@ lazy_static! {
     };
 
     // use a tracer that generates valid span IDs (unlike default NoopTracer)
-    static ref TRACER: Tracer = opentelemetry::sdk::export::trace::stdout::new_pipeline()
-        .with_writer(std::io::sink())
-        .install_simple();
+    static ref TRACER_PROVIDER: opentelemetry::sdk::trace::TracerProvider =
+        opentelemetry::sdk::trace::TracerProvider::builder().build();
+    static ref TRACER: Tracer = TRACER_PROVIDER.tracer("tracing-stackdriver");
 }
 
 #[derive(Debug, Deserialize)]
@@ -39,7 +41,7 @@ struct MockEventWithCloudTraceFields {
     span_id: SpanId,
     #[serde(rename = "logging.googleapis.com/trace")]
     trace_id: String,
-    #[serde(rename = "logging.googleapis.com/trace_sampled", default)]
+    #[serde(rename = "logging.googleapis.com/trace_sampled")]
     trace_sampled: bool,
 }
 
@@ -54,6 +56,24 @@ where
 fn test_with_tracing<M>(span_id: SpanId, trace_id: TraceId, make_writer: M, callback: impl FnOnce())
 where
     M: for<'writer> MakeWriter<'writer> + Sync + Send + 'static,
+{
+    test_with_sampled_tracing(
+        span_id,
+        trace_id,
+        TraceFlags::default(),
+        make_writer,
+        callback,
+    )
+}
+
+fn test_with_sampled_tracing<M>(
+    span_id: SpanId,
+    trace_id: TraceId,
+    trace_flags: TraceFlags,
+    make_writer: M,
+    callback: impl FnOnce(),
+) where
+    M: for<'writer> MakeWriter<'writer> + Sync + Send + 'static,
 {
     // generate the tracing subscriber
     let subscriber = tracing_subscriber::registry()
@@ -74,7 +94,7 @@ where
     let context = opentelemetry::Context::current_with_span(TestSpan(SpanContext::new(
         trace_id,
         span_id,
-        TraceFlags::default(),
+        trace_flags,
         false,
         TraceState::default(),
     )));
@@ -186,3 +206,265 @@ fn handles_nested_spans() {
         assert!(!message.trace_sampled)
     }
 }
+
+#[test]
+fn shares_project_id_with_trace_id_fields() {
+    // generate the output buffer
+    let buffer = Arc::new(Mutex::new(vec![]));
+    let shared = buffer.clone();
+    let make_writer = move || MockWriter(shared.clone());
+
+    // generate an event with a manually-recorded trace ID, outside of any OpenTelemetry span
+    test_with_tracing(SpanId::INVALID, TraceId::INVALID, make_writer, || {
+        tracing::info!(trace_id = "abc123", "test event");
+    });
+
+    let output: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(&buffer.try_lock().unwrap())
+            .expect("Error converting test buffer to JSON");
+
+    assert_eq!(
+        output.get("logging.googleapis.com/trace"),
+        Some(&serde_json::json!(format!(
+            "projects/{PROJECT_ID}/traces/abc123"
+        ))),
+    );
+}
+
+#[test]
+fn prefers_opentelemetry_context_over_span_fields() {
+    // generate the output buffer
+    let buffer = Arc::new(Mutex::new(vec![]));
+    let shared = buffer.clone();
+    let make_writer = move || MockWriter(shared.clone());
+
+    // generate relevant IDs
+    let mut rng = rand::thread_rng();
+    let span_id = SpanId::from_u64(rng.gen());
+    let trace_id = TraceId::from_u128(rng.gen());
+
+    // generate an event within a span that also records its own trace fields
+    test_with_tracing(span_id, trace_id, make_writer, || {
+        let root = tracing::debug_span!("root", trace_id = "manual-trace", span_id = "4a");
+        let _root = root.enter();
+        tracing::debug!("test event");
+    });
+
+    let raw = buffer.try_lock().unwrap();
+    let output: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(&raw).expect("Error converting test buffer to JSON");
+
+    // each special field should only be written once
+    let raw = String::from_utf8_lossy(&raw);
+    assert_eq!(raw.matches("logging.googleapis.com/trace\"").count(), 1);
+    assert_eq!(raw.matches("logging.googleapis.com/spanId\"").count(), 1);
+
+    assert_eq!(
+        output.get("logging.googleapis.com/trace"),
+        Some(&serde_json::json!(format!(
+            "projects/{PROJECT_ID}/traces/{trace_id}"
+        ))),
+    );
+    assert_ne!(
+        output.get("logging.googleapis.com/spanId"),
+        Some(&serde_json::json!("000000000000004a")),
+    );
+}
+
+#[test]
+fn falls_back_to_span_fields_without_opentelemetry_layer() {
+    let layer = tracing_stackdriver::layer().with_cloud_trace(CLOUD_TRACE_CONFIGURATION.clone());
+
+    let events = helpers::run_with_tracing_layer::<MockEventWithCloudTraceFields>(layer, || {
+        let span = tracing::info_span!(
+            "request",
+            trace_id = "abc123",
+            span_id = "000000000000004a",
+            trace_sampled = true
+        );
+        let _guard = span.enter();
+        tracing::info!("test event");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(
+        event.trace_id,
+        format!("projects/{PROJECT_ID}/traces/abc123")
+    );
+    assert_eq!(event.span_id, SpanId::from_u64(74));
+    assert!(event.trace_sampled);
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithTraceSampled {
+    #[serde(rename = "logging.googleapis.com/trace_sampled")]
+    trace_sampled: Option<bool>,
+}
+
+fn trace_sampled_with_flags(trace_flags: TraceFlags) -> Option<bool> {
+    // generate the output buffer
+    let buffer = Arc::new(Mutex::new(vec![]));
+    let shared = buffer.clone();
+    let make_writer = move || MockWriter(shared.clone());
+
+    // generate relevant IDs
+    let mut rng = rand::thread_rng();
+    let span_id = SpanId::from_u64(rng.gen());
+    let trace_id = TraceId::from_u128(rng.gen());
+
+    test_with_sampled_tracing(span_id, trace_id, trace_flags, make_writer, || {
+        let root = tracing::debug_span!("root");
+        let _root = root.enter();
+        tracing::debug!("test event");
+    });
+
+    let output: MockEventWithTraceSampled = serde_json::from_slice(&buffer.try_lock().unwrap())
+        .expect("Error converting test buffer to JSON");
+
+    output.trace_sampled
+}
+
+#[test]
+fn marks_sampled_traces() {
+    assert_eq!(trace_sampled_with_flags(TraceFlags::SAMPLED), Some(true));
+}
+
+#[test]
+fn marks_unsampled_traces() {
+    assert_eq!(trace_sampled_with_flags(TraceFlags::default()), Some(false));
+}
+
+#[test]
+fn omits_trace_sampled_without_opentelemetry_context() {
+    let layer = tracing_stackdriver::layer().with_cloud_trace(CLOUD_TRACE_CONFIGURATION.clone());
+
+    let events = helpers::run_with_tracing_layer::<MockEventWithTraceSampled>(layer, || {
+        let root = tracing::debug_span!("root");
+        let _root = root.enter();
+        tracing::debug!("test event");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.trace_sampled, None);
+}
+
+fn root_trace_sampled_with_sampler(
+    sampler: opentelemetry::sdk::trace::Sampler,
+    sample: impl FnOnce(&tracing::Span),
+) -> Option<bool> {
+    let buffer = Arc::new(Mutex::new(vec![]));
+    let shared = buffer.clone();
+    let make_writer = move || MockWriter(shared.clone());
+
+    let tracer_provider = opentelemetry::sdk::trace::TracerProvider::builder()
+        .with_config(opentelemetry::sdk::trace::config().with_sampler(sampler))
+        .build();
+
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(tracer_provider.tracer("root")))
+        .with(
+            tracing_stackdriver::layer()
+                .with_writer(make_writer)
+                .with_project_id(PROJECT_ID),
+        );
+
+    tracing::subscriber::with_default(subscriber, || {
+        let root = tracing::info_span!("root");
+        let _root = root.enter();
+        sample(&root);
+        tracing::info!("test event");
+    });
+
+    let output: MockEventWithTraceSampled = serde_json::from_slice(&buffer.try_lock().unwrap())
+        .expect("Error converting test buffer to JSON");
+
+    output.trace_sampled
+}
+
+#[test]
+fn marks_sampled_root_spans() {
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let trace_sampled =
+        root_trace_sampled_with_sampler(opentelemetry::sdk::trace::Sampler::AlwaysOn, |root| {
+            // propagating a span's context makes its sampling decision
+            root.context();
+        });
+
+    assert_eq!(trace_sampled, Some(true));
+}
+
+#[test]
+fn marks_sampled_root_spans_with_child_spans() {
+    let trace_sampled =
+        root_trace_sampled_with_sampler(opentelemetry::sdk::trace::Sampler::AlwaysOn, |_| {
+            let _child = tracing::info_span!("child");
+        });
+
+    assert_eq!(trace_sampled, Some(true));
+}
+
+#[test]
+fn marks_unsampled_root_spans() {
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let trace_sampled =
+        root_trace_sampled_with_sampler(opentelemetry::sdk::trace::Sampler::AlwaysOff, |root| {
+            root.context();
+        });
+
+    assert_eq!(trace_sampled, Some(false));
+}
+
+#[test]
+fn omits_trace_sampled_before_root_spans_are_sampled() {
+    let trace_sampled =
+        root_trace_sampled_with_sampler(opentelemetry::sdk::trace::Sampler::AlwaysOn, |_| {});
+
+    assert_eq!(trace_sampled, None);
+}
+
+#[test]
+fn bridges_opentelemetry_context_with_project_id() {
+    // generate the output buffer
+    let buffer = Arc::new(Mutex::new(vec![]));
+    let shared = buffer.clone();
+    let make_writer = move || MockWriter(shared.clone());
+
+    // register an OpenTelemetry layer without any explicit Cloud Trace configuration
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(TRACER.clone()))
+        .with(
+            tracing_stackdriver::layer()
+                .with_writer(make_writer)
+                .with_project_id(PROJECT_ID),
+        );
+
+    let trace_id = TraceId::from_u128(rand::thread_rng().gen());
+    let context = opentelemetry::Context::current_with_span(TestSpan(SpanContext::new(
+        trace_id,
+        SpanId::from_u64(1),
+        TraceFlags::SAMPLED,
+        false,
+        TraceState::default(),
+    )));
+    let _context = context.attach();
+
+    tracing::subscriber::with_default(subscriber, || {
+        let root = tracing::info_span!("root");
+        let _root = root.enter();
+        tracing::info!("test event");
+    });
+
+    let output: MockEventWithCloudTraceFields = serde_json::from_slice(&buffer.try_lock().unwrap())
+        .expect("Error converting test buffer to JSON");
+
+    assert_eq!(
+        output.trace_id,
+        format!("projects/{PROJECT_ID}/traces/{trace_id}")
+    );
+    assert_ne!(output.span_id, SpanId::INVALID);
+    assert!(output.trace_sampled);
+}
@@ -0,0 +1,94 @@
+use helpers::run_with_tracing;
+use serde::Deserialize;
+
+mod helpers;
+mod mocks;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct MockOperation {
+    id: Option<String>,
+    producer: Option<String>,
+    first: Option<bool>,
+    last: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithOperation {
+    #[serde(rename = "logging.googleapis.com/operation")]
+    operation: Option<MockOperation>,
+}
+
+#[test]
+fn nests_operation() {
+    let events = run_with_tracing::<MockEventWithOperation>(|| {
+        tracing::info!(
+            operation.id = "batch-123",
+            operation.producer = "github.com/MyProject/MyApplication",
+            operation.first = true,
+            "starting batch"
+        );
+        tracing::info!(
+            operation.id = "batch-123",
+            operation.last = true,
+            "finished batch"
+        );
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let mut events = events.into_iter();
+    let first = events.next().expect("No first event heard");
+    let last = events.next().expect("No last event heard");
+
+    assert_eq!(
+        first.operation,
+        Some(MockOperation {
+            id: Some("batch-123".into()),
+            producer: Some("github.com/MyProject/MyApplication".into()),
+            first: Some(true),
+            last: None,
+        })
+    );
+    assert_eq!(
+        last.operation,
+        Some(MockOperation {
+            id: Some("batch-123".into()),
+            producer: None,
+            first: None,
+            last: Some(true),
+        })
+    );
+}
+
+#[test]
+fn omits_operation_by_default() {
+    let events = run_with_tracing::<MockEventWithOperation>(|| tracing::info!("hello!"))
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert!(event.operation.is_none());
+}
+
+#[test]
+fn inherits_span_operation_fields() {
+    let events = run_with_tracing::<MockEventWithOperation>(|| {
+        let span = tracing::info_span!(
+            "batch",
+            operation.id = "batch-123",
+            operation.producer = "my-batch-job"
+        );
+        let _guard = span.enter();
+        tracing::info!(operation.last = "true", "finished batch");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(
+        event.operation,
+        Some(MockOperation {
+            id: Some("batch-123".into()),
+            producer: Some("my-batch-job".into()),
+            first: None,
+            last: Some(true),
+        })
+    );
+}
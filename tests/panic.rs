@@ -0,0 +1,116 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+use tracing_stackdriver::{LogSeverity, PanicHook, ServiceContext};
+
+/// Panic hooks are global, so tests that install them can't run in parallel
+static PANIC_HOOK: Mutex<()> = Mutex::new(());
+
+#[test]
+fn reports_panics_before_calling_previous_hooks() {
+    let _guard = PANIC_HOOK.lock().unwrap_or_else(|error| error.into_inner());
+    let called_previous_hook = Arc::new(AtomicBool::new(false));
+    let called = called_previous_hook.clone();
+
+    std::panic::set_hook(Box::new(move |_| called.store(true, Ordering::SeqCst)));
+    tracing_stackdriver::install_panic_hook();
+
+    let events = tracing_stackdriver::layer()
+        .with_error_reporting(ServiceContext {
+            service: "checkout".into(),
+            version: None,
+        })
+        .as_json(|| {
+            let _ = std::panic::catch_unwind(|| panic!("something broke"));
+        })
+        .expect("Error converting events to JSON");
+
+    let _ = std::panic::take_hook();
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["severity"], "ERROR");
+    let message = event["message"].as_str().expect("No message found");
+    let (message, stack_trace) = message.split_once('\n').expect("No stack trace in message");
+    assert_eq!(message, "panicked: something broke");
+    assert!(stack_trace.contains("reports_panics_before_calling_previous_hooks"));
+    assert_eq!(event["stack_trace"], stack_trace);
+    assert!(event["panicLocation"]
+        .as_str()
+        .expect("No panicLocation found")
+        .starts_with(file!()));
+    assert_eq!(
+        event["@type"],
+        "type.googleapis.com/google.devtools.clouderrorreporting.v1beta1.ReportedErrorEvent"
+    );
+    assert_eq!(event["serviceContext"]["service"], "checkout");
+    assert!(event.get("context").is_none());
+    assert!(called_previous_hook.load(Ordering::SeqCst));
+}
+
+#[test]
+fn reports_panics_with_configured_severity_and_service_context() {
+    let _guard = PANIC_HOOK.lock().unwrap_or_else(|error| error.into_inner());
+
+    PanicHook::new()
+        .with_severity(LogSeverity::Critical)
+        .with_service_context(ServiceContext {
+            service: "payments".into(),
+            version: Some("1.2.3".into()),
+        })
+        .install();
+
+    let events = tracing_stackdriver::layer()
+        .with_error_reporting(ServiceContext {
+            service: "checkout".into(),
+            version: None,
+        })
+        .as_json(|| {
+            let _ = std::panic::catch_unwind(|| panic!("something broke"));
+        })
+        .expect("Error converting events to JSON");
+
+    let _ = std::panic::take_hook();
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["severity"], "CRITICAL");
+    assert!(event["message"]
+        .as_str()
+        .expect("No message found")
+        .starts_with("panicked: something broke\n"));
+    assert_eq!(event["serviceContext"]["service"], "payments");
+    assert_eq!(event["serviceContext"]["version"], "1.2.3");
+    assert!(event.get("severityValue").is_none());
+    assert!(event.get("serviceContextValue").is_none());
+}
+
+#[test]
+fn reports_panics_with_service_context_without_error_reporting_layer() {
+    let _guard = PANIC_HOOK.lock().unwrap_or_else(|error| error.into_inner());
+
+    PanicHook::new()
+        .with_service_context(ServiceContext {
+            service: "payments".into(),
+            version: None,
+        })
+        .install();
+
+    let events = tracing_stackdriver::layer()
+        .as_json(|| {
+            let _ = std::panic::catch_unwind(|| panic!("something broke"));
+        })
+        .expect("Error converting events to JSON");
+
+    let _ = std::panic::take_hook();
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["severity"], "ERROR");
+    assert_eq!(
+        event["@type"],
+        "type.googleapis.com/google.devtools.clouderrorreporting.v1beta1.ReportedErrorEvent"
+    );
+    assert_eq!(
+        event["serviceContext"],
+        serde_json::json!({ "service": "payments" })
+    );
+}
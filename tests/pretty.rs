@@ -0,0 +1,55 @@
+use helpers::MockWriter;
+use mocks::MockDefaultEvent;
+use std::sync::{Arc, Mutex};
+use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+mod helpers;
+mod mocks;
+
+fn run_with_pretty_tracing(pretty: bool, callback: impl FnOnce()) -> String {
+    let buffer = Arc::new(Mutex::new(vec![]));
+    let shared = buffer.clone();
+    let make_writer = move || MockWriter(shared.clone());
+    let stackdriver = tracing_stackdriver::layer()
+        .with_pretty(pretty)
+        .with_writer(make_writer);
+    let subscriber = Registry::default().with(stackdriver);
+
+    tracing::subscriber::with_default(subscriber, callback);
+
+    let buffer = buffer
+        .lock()
+        .expect("Couldn't get lock on test write target");
+
+    String::from_utf8(buffer.clone()).expect("Invalid UTF-8 in test buffer")
+}
+
+#[test]
+fn writes_pretty_json() {
+    let output = run_with_pretty_tracing(true, || {
+        tracing::info!("first message");
+        tracing::warn!("second message");
+    });
+
+    assert!(output.contains("\n  \"severity\": \"INFO\""));
+    assert!(output.ends_with("}\n"));
+
+    let events: Vec<MockDefaultEvent> = serde_json::Deserializer::from_str(&output)
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .expect("Error converting test buffer to JSON");
+
+    let severities: Vec<_> = events.iter().map(|event| event.severity.as_str()).collect();
+    assert_eq!(severities, ["INFO", "WARNING"]);
+}
+
+#[test]
+fn writes_compact_json_by_default() {
+    let output = run_with_pretty_tracing(false, || {
+        tracing::info!("first message");
+        tracing::warn!("second message");
+    });
+
+    assert_eq!(output.lines().count(), 2);
+    assert!(output.ends_with("}\n"));
+}
@@ -0,0 +1,67 @@
+use helpers::run_with_tracing_layer;
+use serde::Deserialize;
+
+mod helpers;
+
+#[derive(Debug, Deserialize)]
+struct MockProcess {
+    pid: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct MockHost {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithProcess {
+    process: Option<MockProcess>,
+    host: Option<MockHost>,
+}
+
+#[test]
+fn excludes_process_fields_by_default() {
+    let events =
+        run_with_tracing_layer::<MockEventWithProcess>(tracing_stackdriver::layer(), || {
+            tracing::info!("hello!")
+        })
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert!(event.process.is_none());
+    assert!(event.host.is_none());
+}
+
+#[test]
+fn includes_pids() {
+    let layer = tracing_stackdriver::layer().with_pid(true);
+
+    let events = run_with_tracing_layer::<MockEventWithProcess>(layer, || tracing::info!("hello!"))
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    let process = event.process.as_ref().expect("No process found");
+    assert_eq!(process.pid, std::process::id());
+}
+
+#[test]
+fn includes_hostnames() {
+    let layer = tracing_stackdriver::layer().with_hostname(true);
+
+    let events = run_with_tracing_layer::<MockEventWithProcess>(layer, || {
+        tracing::info!("first");
+        tracing::info!("second");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let hostnames: Vec<_> = events
+        .iter()
+        .filter_map(|event| event.host.as_ref())
+        .map(|host| host.name.as_str())
+        .collect();
+
+    // hostnames are resolved on a best-effort basis, but are the same for every event
+    assert!(matches!(hostnames.len(), 0 | 2));
+    assert!(hostnames.iter().all(|hostname| !hostname.is_empty()));
+    assert!(hostnames.windows(2).all(|pair| pair[0] == pair[1]));
+}
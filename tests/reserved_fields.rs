@@ -0,0 +1,168 @@
+use helpers::{run_with_tracing, run_with_tracing_layer, MockWriter};
+use std::sync::{Arc, Mutex};
+use tracing_stackdriver::ServiceContext;
+use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+mod helpers;
+
+/// Number of times a key appears at the root of a serialized event
+fn count_keys(line: &str, key: &str) -> usize {
+    line.matches(&format!("\"{key}\":")).count()
+}
+
+#[test]
+fn renames_invalid_severity_fields() {
+    let events = run_with_tracing::<serde_json::Value>(|| {
+        tracing::error!(severity = "SEVERE", "severe failure")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["severity"], "ERROR");
+    assert_eq!(event["severityValue"], "SEVERE");
+}
+
+#[test]
+fn renames_time_fields() {
+    let events = run_with_tracing::<serde_json::Value>(|| {
+        tracing::info!(time = "yesterday", "shadowed time")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_ne!(event["time"], "yesterday");
+    assert_eq!(event["timeValue"], "yesterday");
+}
+
+#[test]
+fn renames_target_fields() {
+    let events = run_with_tracing::<serde_json::Value>(|| {
+        tracing::info!(target = "shadowed", "shadowed target")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["target"], "reserved_fields");
+    assert_eq!(event["targetValue"], "shadowed");
+}
+
+#[test]
+fn keeps_target_fields_without_targets() {
+    let layer = tracing_stackdriver::layer().with_target(false);
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::info!(target = "kept", "no target")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["target"], "kept");
+}
+
+#[test]
+fn writes_message_fields_once() {
+    let events =
+        run_with_tracing::<serde_json::Value>(|| tracing::info!(message = "field message"))
+            .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["message"], "field message");
+}
+
+#[test]
+fn renames_span_fields() {
+    let events = run_with_tracing::<serde_json::Value>(|| {
+        let span = tracing::info_span!("request");
+        let _guard = span.enter();
+        tracing::info!(span = "shadowed", spans = 2, "shadowed spans")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["span"]["name"], "request");
+    assert_eq!(event["spanValue"], "shadowed");
+    assert_eq!(event["spansValue"], 2);
+}
+
+#[test]
+fn keeps_span_fields_outside_of_spans() {
+    let events = run_with_tracing::<serde_json::Value>(|| tracing::info!(span = "kept", "no span"))
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["span"], "kept");
+    assert!(event.get("spanValue").is_none());
+}
+
+#[test]
+fn only_renames_service_context_fields_of_reported_events() {
+    let layer = tracing_stackdriver::layer().with_error_reporting(ServiceContext {
+        service: "checkout".into(),
+        version: None,
+    });
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::info!(service_context = "kept", "unreported");
+        tracing::error!(service_context = "shadowed", "reported");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    assert_eq!(events[0]["serviceContext"], "kept");
+    assert!(events[0].get("serviceContextValue").is_none());
+    assert_eq!(events[1]["serviceContext"]["service"], "checkout");
+    assert_eq!(events[1]["serviceContextValue"], "shadowed");
+}
+
+#[test]
+fn never_writes_duplicate_keys() {
+    let buffer = Arc::new(Mutex::new(vec![]));
+    let shared = buffer.clone();
+    let make_writer = move || MockWriter(shared.clone());
+    let subscriber = Registry::default().with(
+        tracing_stackdriver::layer()
+            .with_max_field_bytes(Some(4))
+            .with_writer(make_writer),
+    );
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!(
+            severity = "bogus",
+            time = 1,
+            time_value = 2,
+            target = "shadowed",
+            body = "too long",
+            body_truncated = "shadowed",
+            http_request = "shadowed",
+            http_request.status = 200,
+            "every reserved field"
+        )
+    });
+
+    let buffer = buffer
+        .lock()
+        .expect("Couldn't get lock on test write target");
+    let line = std::str::from_utf8(&buffer).expect("Invalid UTF-8");
+
+    for key in [
+        "severity",
+        "time",
+        "timeValue",
+        "timeValueValue",
+        "target",
+        "body",
+        "bodyTruncated",
+        "bodyTruncatedValue",
+        "httpRequest",
+        "httpRequestValue",
+        "message",
+    ] {
+        assert_eq!(count_keys(line, key), 1, "duplicate {key} in {line}");
+    }
+
+    let event: serde_json::Value = serde_json::from_str(line).expect("Invalid JSON");
+    assert_eq!(event["timeValue"], 2);
+    assert_eq!(event["timeValueValue"], 1);
+    assert_eq!(event["bodyTruncated"], true);
+    assert_eq!(event["bodyTruncatedValue"], "s…");
+    assert_eq!(event["httpRequest"]["status"], 200);
+}
@@ -0,0 +1,43 @@
+use helpers::run_with_tracing_layer;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+mod helpers;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct MockResource {
+    r#type: String,
+    labels: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithResource {
+    resource: MockResource,
+}
+
+#[test]
+fn includes_monitored_resources() {
+    let labels = BTreeMap::from([
+        ("instance_id".to_owned(), "1234567890".to_owned()),
+        ("zone".to_owned(), "us-central1-a".to_owned()),
+    ]);
+
+    let layer =
+        tracing_stackdriver::layer().with_resource(tracing_stackdriver::MonitoredResource {
+            resource_type: "gce_instance".to_owned(),
+            labels: labels.clone(),
+        });
+
+    let events =
+        run_with_tracing_layer::<MockEventWithResource>(layer, || tracing::info!("resourceful"))
+            .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(
+        event.resource,
+        MockResource {
+            r#type: "gce_instance".to_owned(),
+            labels,
+        }
+    );
+}
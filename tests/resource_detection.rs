@@ -0,0 +1,72 @@
+#![cfg(feature = "resource-detection")]
+use helpers::run_with_tracing_layer;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+mod helpers;
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithLabels {
+    #[serde(rename = "logging.googleapis.com/labels")]
+    labels: BTreeMap<String, String>,
+}
+
+// environment variables are process-wide, so every variation is detected within a single test
+#[test]
+fn detects_resources_from_environment() {
+    for variable in [
+        "K_SERVICE",
+        "K_REVISION",
+        "K_CONFIGURATION",
+        "KUBERNETES_SERVICE_HOST",
+    ] {
+        std::env::remove_var(variable);
+    }
+
+    std::env::set_var("K_SERVICE", "my-service");
+    std::env::set_var("K_REVISION", "my-service-00001-abc");
+    std::env::set_var("K_CONFIGURATION", "my-service");
+    let cloud_run = tracing_stackdriver::detect_resource();
+
+    for variable in ["K_SERVICE", "K_REVISION", "K_CONFIGURATION"] {
+        std::env::remove_var(variable);
+    }
+
+    std::env::set_var("KUBERNETES_SERVICE_HOST", "10.0.0.1");
+    let kubernetes = tracing_stackdriver::detect_resource();
+    std::env::remove_var("KUBERNETES_SERVICE_HOST");
+
+    assert_eq!(
+        cloud_run.resource_type.as_deref(),
+        Some("cloud_run_revision")
+    );
+    assert_eq!(cloud_run.labels["service_name"], "my-service");
+    assert_eq!(cloud_run.labels["revision_name"], "my-service-00001-abc");
+    assert_eq!(cloud_run.labels["configuration_name"], "my-service");
+    assert_eq!(kubernetes.resource_type.as_deref(), Some("k8s_container"));
+
+    let layer = tracing_stackdriver::layer().with_labels(cloud_run.labels);
+
+    let events = run_with_tracing_layer::<MockEventWithLabels>(layer, || tracing::info!("hello!"))
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.labels["service_name"], "my-service");
+}
+
+#[test]
+fn converts_detected_resources_with_types() {
+    let untyped = tracing_stackdriver::DetectedResource::default();
+    let typed = tracing_stackdriver::DetectedResource {
+        resource_type: Some("k8s_container".to_owned()),
+        ..Default::default()
+    };
+
+    assert_eq!(untyped.into_monitored_resource(), None);
+    assert_eq!(
+        typed
+            .into_monitored_resource()
+            .map(|resource| resource.resource_type),
+        Some("k8s_container".to_owned())
+    );
+}
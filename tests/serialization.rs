@@ -0,0 +1,82 @@
+use helpers::run_with_tracing;
+use mocks::MockDefaultEvent;
+use serde::Deserialize;
+use std::fmt;
+
+mod helpers;
+mod mocks;
+
+/// Field value whose Debug implementation always fails
+struct Unformattable;
+
+impl fmt::Debug for Unformattable {
+    fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Err(fmt::Error)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithBrokenField {
+    severity: String,
+    message: String,
+    broken: String,
+    working: u8,
+}
+
+#[test]
+fn substitutes_placeholders_for_unserializable_fields() {
+    let events = run_with_tracing::<MockEventWithBrokenField>(
+        || tracing::warn!(broken = ?Unformattable, working = 1, "still logged"),
+    )
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.severity, "WARNING");
+    assert_eq!(event.message, "still logged");
+    assert_eq!(event.broken, "<unserializable>");
+    assert_eq!(event.working, 1);
+}
+
+#[test]
+fn keeps_time_for_unserializable_fields() {
+    let events = run_with_tracing::<MockDefaultEvent>(
+        || tracing::info!(broken = ?Unformattable, "still logged"),
+    )
+    .expect("Error converting test buffer to JSON");
+
+    assert_eq!(events.len(), 1);
+}
+
+#[test]
+fn preserves_numeric_and_boolean_types() {
+    let events = run_with_tracing::<serde_json::Value>(|| {
+        tracing::info!(
+            count = 42u64,
+            offset = -7i64,
+            ratio = 0.5f64,
+            huge = u64::MAX,
+            cached = true,
+            "typed fields"
+        )
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["count"], serde_json::json!(42));
+    assert_eq!(event["offset"], serde_json::json!(-7));
+    assert_eq!(event["ratio"], serde_json::json!(0.5));
+    assert_eq!(event["huge"].as_u64(), Some(u64::MAX));
+    assert_eq!(event["cached"], serde_json::json!(true));
+}
+
+#[test]
+fn keeps_non_finite_floats_as_strings() {
+    let events = run_with_tracing::<serde_json::Value>(|| {
+        tracing::info!(ratio = f64::NAN, limit = f64::INFINITY, "non-finite fields")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["ratio"], "NaN");
+    assert_eq!(event["limit"], "inf");
+}
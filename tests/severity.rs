@@ -0,0 +1,291 @@
+use helpers::{run_with_tracing_layer, run_with_tracing_warnings};
+use mocks::MockDefaultEvent;
+use tracing::Level;
+use tracing_stackdriver::{LogSeverity, SeverityFormat, TraceSeverity};
+
+mod helpers;
+mod mocks;
+
+#[test]
+fn uses_custom_severity_mapping() {
+    let layer = tracing_stackdriver::layer().with_severity_mapping(|level| match *level {
+        Level::INFO => LogSeverity::Notice,
+        _ => LogSeverity::from(level),
+    });
+
+    let events = run_with_tracing_layer::<MockDefaultEvent>(layer, || {
+        tracing::info!("notice me!");
+        tracing::trace!("don't notice me!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let mut events = events.into_iter();
+    let info = events.next().expect("No info event heard");
+    let trace = events.next().expect("No trace event heard");
+    assert_eq!(info.severity, "NOTICE");
+    assert_eq!(trace.severity, "DEBUG");
+}
+
+#[test]
+fn prefers_severity_overrides_over_custom_severity_mapping() {
+    let layer = tracing_stackdriver::layer().with_severity_mapping(|_| LogSeverity::Notice);
+
+    let events = run_with_tracing_layer::<MockDefaultEvent>(layer, || {
+        tracing::info!(severity = "alert", "alert me!")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.severity, "ALERT");
+}
+
+#[test]
+fn overrides_severity_with_valid_severity_fields() {
+    let events = run_with_tracing_layer::<MockDefaultEvent>(tracing_stackdriver::layer(), || {
+        tracing::error!(severity = "CRITICAL", "critical failure")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.severity, "CRITICAL");
+}
+
+#[test]
+fn ignores_invalid_severity_fields() {
+    let events = run_with_tracing_layer::<MockDefaultEvent>(tracing_stackdriver::layer(), || {
+        tracing::error!(severity = "SEVERE", "severe failure")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.severity, "ERROR");
+}
+
+#[test]
+fn warns_about_invalid_severity_fields() {
+    let (events, warnings) =
+        run_with_tracing_warnings::<MockDefaultEvent>(tracing_stackdriver::layer(), || {
+            tracing::error!(severity = "SEVERE", "severe failure")
+        })
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.severity, "ERROR");
+    assert_eq!(
+        warnings,
+        ["unrecognized severity \"SEVERE\", falling back to ERROR"]
+    );
+}
+
+#[test]
+fn omits_warnings_about_valid_severity_fields() {
+    let (_, warnings) =
+        run_with_tracing_warnings::<MockDefaultEvent>(tracing_stackdriver::layer(), || {
+            tracing::error!(severity = "CRITICAL", "critical failure")
+        })
+        .expect("Error converting test buffer to JSON");
+
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn overrides_severity_with_every_known_severity() {
+    let severities = [
+        "DEFAULT",
+        "DEBUG",
+        "INFO",
+        "NOTICE",
+        "WARNING",
+        "ERROR",
+        "CRITICAL",
+        "ALERT",
+        "EMERGENCY",
+    ];
+
+    // deserializing into a struct also guards against duplicate severity keys
+    let events = run_with_tracing_layer::<MockDefaultEvent>(tracing_stackdriver::layer(), || {
+        for severity in severities {
+            tracing::info!(severity, "overridden");
+        }
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let emitted: Vec<_> = events.iter().map(|event| event.severity.as_str()).collect();
+    assert_eq!(emitted, severities);
+}
+
+#[test]
+fn overrides_severity_with_extended_log_severities() {
+    let events = run_with_tracing_layer::<MockDefaultEvent>(tracing_stackdriver::layer(), || {
+        tracing::error!(severity = %LogSeverity::Critical, "critical");
+        tracing::error!(severity = %LogSeverity::Alert, "alert");
+        tracing::error!(severity = %LogSeverity::Emergency, "emergency");
+        tracing::info!(severity = %LogSeverity::Notice, "notice");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let emitted: Vec<_> = events.iter().map(|event| event.severity.as_str()).collect();
+    assert_eq!(emitted, ["CRITICAL", "ALERT", "EMERGENCY", "NOTICE"]);
+}
+
+#[test]
+fn converts_levels_to_log_severities() {
+    assert_eq!(LogSeverity::from(Level::TRACE), LogSeverity::Debug);
+    assert_eq!(LogSeverity::from(Level::DEBUG), LogSeverity::Debug);
+    assert_eq!(LogSeverity::from(Level::INFO), LogSeverity::Info);
+    assert_eq!(LogSeverity::from(Level::WARN), LogSeverity::Warning);
+    assert_eq!(LogSeverity::from(Level::ERROR), LogSeverity::Error);
+}
+
+#[test]
+fn round_trips_log_severity_names() {
+    let severities = [
+        (LogSeverity::Default, "DEFAULT"),
+        (LogSeverity::Debug, "DEBUG"),
+        (LogSeverity::Info, "INFO"),
+        (LogSeverity::Notice, "NOTICE"),
+        (LogSeverity::Warning, "WARNING"),
+        (LogSeverity::Error, "ERROR"),
+        (LogSeverity::Critical, "CRITICAL"),
+        (LogSeverity::Alert, "ALERT"),
+        (LogSeverity::Emergency, "EMERGENCY"),
+    ];
+
+    for (severity, name) in severities {
+        assert_eq!(severity.to_string(), name);
+        assert_eq!(name.parse::<LogSeverity>(), Ok(severity));
+        assert_eq!(name.to_lowercase().parse::<LogSeverity>(), Ok(severity));
+        assert_eq!(
+            serde_json::to_value(severity).expect("Error serializing LogSeverity"),
+            name
+        );
+    }
+}
+
+#[test]
+fn remaps_warnings_to_notices() {
+    let layer = tracing_stackdriver::layer().with_severity_mapping(|level| match *level {
+        Level::WARN => LogSeverity::Notice,
+        _ => LogSeverity::from(level),
+    });
+
+    let events = run_with_tracing_layer::<MockDefaultEvent>(layer, || {
+        tracing::warn!("a notice");
+        tracing::error!("an error");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let emitted: Vec<_> = events.iter().map(|event| event.severity.as_str()).collect();
+    assert_eq!(emitted, ["NOTICE", "ERROR"]);
+}
+
+#[test]
+fn matches_default_severity_mapping() {
+    let log_every_level = || {
+        tracing::trace!("trace");
+        tracing::debug!("debug");
+        tracing::info!("info");
+        tracing::warn!("warn");
+        tracing::error!("error");
+    };
+
+    let default_events =
+        run_with_tracing_layer::<MockDefaultEvent>(tracing_stackdriver::layer(), log_every_level)
+            .expect("Error converting test buffer to JSON");
+
+    let layer =
+        tracing_stackdriver::layer().with_severity_mapping(|level| LogSeverity::from(level));
+    let mapped_events = run_with_tracing_layer::<MockDefaultEvent>(layer, log_every_level)
+        .expect("Error converting test buffer to JSON");
+
+    let default_severities: Vec<_> = default_events.iter().map(|event| &event.severity).collect();
+    let mapped_severities: Vec<_> = mapped_events.iter().map(|event| &event.severity).collect();
+    assert_eq!(default_severities, mapped_severities);
+    assert_eq!(
+        default_severities,
+        ["DEBUG", "DEBUG", "INFO", "WARNING", "ERROR"]
+    );
+}
+
+#[test]
+fn maps_trace_events_to_default_severity() {
+    let layer = tracing_stackdriver::layer().with_trace_severity(TraceSeverity::Default);
+
+    let events = run_with_tracing_layer::<MockDefaultEvent>(layer, || {
+        tracing::trace!("trace");
+        tracing::debug!("debug");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let mut events = events.into_iter();
+    let trace = events.next().expect("No trace event heard");
+    let debug = events.next().expect("No debug event heard");
+    assert_eq!(trace.severity, "DEFAULT");
+    assert_eq!(debug.severity, "DEBUG");
+}
+
+#[test]
+fn prefers_custom_severity_mapping_over_trace_severity() {
+    let layer = tracing_stackdriver::layer()
+        .with_trace_severity(TraceSeverity::Default)
+        .with_severity_mapping(|_| LogSeverity::Notice);
+
+    let events = run_with_tracing_layer::<MockDefaultEvent>(layer, || tracing::trace!("trace"))
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.severity, "NOTICE");
+}
+
+#[test]
+fn converts_log_severities_to_numbers() {
+    let severities = [
+        (LogSeverity::Default, 0),
+        (LogSeverity::Debug, 100),
+        (LogSeverity::Info, 200),
+        (LogSeverity::Notice, 300),
+        (LogSeverity::Warning, 400),
+        (LogSeverity::Error, 500),
+        (LogSeverity::Critical, 600),
+        (LogSeverity::Alert, 700),
+        (LogSeverity::Emergency, 800),
+    ];
+
+    for (severity, number) in severities {
+        assert_eq!(i32::from(severity), number, "Wrong number for {severity}");
+    }
+}
+
+#[test]
+fn formats_severities_as_numbers() {
+    let layer = tracing_stackdriver::layer().with_severity_format(SeverityFormat::Number);
+
+    let events =
+        run_with_tracing_layer::<serde_json::Map<String, serde_json::Value>>(layer, || {
+            tracing::warn!("warning");
+            tracing::info!(severity = "critical", "critical failure");
+        })
+        .expect("Error converting test buffer to JSON");
+
+    let mut events = events.into_iter();
+    let warning = events.next().expect("No warning event heard");
+    let critical = events.next().expect("No critical event heard");
+    assert_eq!(warning["severity"], 400);
+    assert_eq!(critical["severity"], 600);
+    assert!(!warning.contains_key("severityNumber"));
+}
+
+#[test]
+fn formats_severities_as_strings_and_numbers() {
+    let layer = tracing_stackdriver::layer().with_severity_format(SeverityFormat::Both);
+
+    let events =
+        run_with_tracing_layer::<serde_json::Map<String, serde_json::Value>>(layer, || {
+            tracing::error!("error")
+        })
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["severity"], "ERROR");
+    assert_eq!(event["severityNumber"], 500);
+}
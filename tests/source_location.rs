@@ -13,6 +13,10 @@ fn includes_source_location() {
     assert!(event.source_location.file.ends_with("source_location.rs"));
     assert!(!event.source_location.line.is_empty());
     assert!(event.source_location.line != "0");
+    assert_eq!(
+        event.source_location.function.as_deref(),
+        Some(module_path!())
+    );
 }
 
 #[test]
@@ -22,3 +26,20 @@ fn excludes_source_location() {
     run_with_tracing_layer::<MockDefaultEvent>(layer, || tracing::info!("hello!"))
         .expect_err("Failed to exclude source location fields from events");
 }
+
+#[test]
+fn reenables_source_location() {
+    let layer = tracing_stackdriver::layer()
+        .with_source_location(false)
+        .with_source_location(true);
+
+    let events = run_with_tracing_layer::<MockDefaultEvent>(layer, || tracing::info!("hello!"))
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert!(event.source_location.file.ends_with("source_location.rs"));
+    assert!(
+        event.source_location.line.parse::<u32>().is_ok(),
+        "line should be serialized as a numeric string"
+    );
+}
@@ -0,0 +1,250 @@
+use helpers::run_with_tracing_layer;
+use serde::Deserialize;
+
+mod helpers;
+
+#[derive(Debug, Deserialize)]
+struct MockNamedSpan {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithSpans {
+    span: MockNamedSpan,
+    spans: Vec<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[test]
+fn includes_span_list_from_root() {
+    let events = run_with_tracing_layer::<MockEventWithSpans>(tracing_stackdriver::layer(), || {
+        let request = tracing::info_span!("request", path = "/");
+        let _request = request.enter();
+        let db_query = tracing::info_span!("db_query", table = "users");
+        let _db_query = db_query.enter();
+        let retry = tracing::info_span!("retry", attempt = 2);
+        let _retry = retry.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    let names: Vec<_> = event.spans.iter().map(|span| &span["name"]).collect();
+    assert_eq!(names, ["request", "db_query", "retry"]);
+    assert_eq!(event.spans[0]["path"], "/");
+    assert_eq!(event.spans[1]["table"], "users");
+    assert_eq!(event.spans[2]["attempt"], 2);
+    assert_eq!(event.span.name, "retry");
+}
+
+#[test]
+fn follows_explicit_parent_spans() {
+    let events = run_with_tracing_layer::<MockEventWithSpans>(tracing_stackdriver::layer(), || {
+        let request = tracing::info_span!("request");
+        let background = tracing::info_span!("background");
+        let _background = background.enter();
+        tracing::info!(parent: &request, "hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.spans.len(), 1);
+    assert_eq!(event.spans[0]["name"], "request");
+}
+
+#[test]
+fn excludes_span_list() {
+    let layer = tracing_stackdriver::layer().with_span_list(false);
+
+    let events =
+        run_with_tracing_layer::<serde_json::Map<String, serde_json::Value>>(layer, || {
+            let span = tracing::info_span!("request");
+            let _guard = span.enter();
+            tracing::info!("hello!");
+        })
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert!(event.contains_key("span"));
+    assert!(!event.contains_key("spans"));
+}
+
+#[test]
+fn flattens_span_fields() {
+    let layer = tracing_stackdriver::layer().with_flattened_spans(true);
+
+    let events =
+        run_with_tracing_layer::<serde_json::Map<String, serde_json::Value>>(layer, || {
+            let span = tracing::info_span!("request", user_id = 42, path = "/span");
+            let _guard = span.enter();
+            tracing::info!(path = "/event", "hello!");
+        })
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert!(!event.contains_key("span"));
+    assert_eq!(event["userId"], 42);
+    assert_eq!(event["path"], "/event");
+    assert_eq!(event["spanName"], "request");
+}
+
+#[test]
+fn flattens_span_names_under_custom_keys() {
+    let layer = tracing_stackdriver::layer()
+        .with_flattened_spans(true)
+        .with_flattened_span_name_field("scope");
+
+    let events =
+        run_with_tracing_layer::<serde_json::Map<String, serde_json::Value>>(layer, || {
+            let span = tracing::info_span!("request");
+            let _guard = span.enter();
+            tracing::info!("hello!");
+        })
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["scope"], "request");
+    assert!(!event.contains_key("spanName"));
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MockSpanTimingEvent {
+    message: String,
+    severity: String,
+    span: MockNamedSpan,
+    elapsed_ms: u64,
+    busy_ns: u64,
+    idle_ns: u64,
+}
+
+#[test]
+fn emits_span_timings_on_close() {
+    let layer = tracing_stackdriver::layer().with_span_timing(true);
+
+    let events = run_with_tracing_layer::<MockSpanTimingEvent>(layer, || {
+        let span = tracing::info_span!("request");
+        let guard = span.enter();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        drop(guard);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No span timing event heard");
+    assert_eq!(event.message, "span closed");
+    assert_eq!(event.severity, "DEBUG");
+    assert_eq!(event.span.name, "request");
+    assert!(event.elapsed_ms >= 15);
+    assert!(event.busy_ns >= 10_000_000);
+    assert!(event.idle_ns >= 5_000_000);
+}
+
+#[test]
+fn omits_span_timings_by_default() {
+    let events = run_with_tracing_layer::<serde_json::Value>(tracing_stackdriver::layer(), || {
+        let span = tracing::info_span!("request");
+        let _guard = span.enter();
+    })
+    .expect("Error converting test buffer to JSON");
+
+    assert!(events.is_empty());
+}
+
+#[derive(Debug, Deserialize)]
+struct MockSpanEvent {
+    message: String,
+    severity: String,
+    span: Option<MockNamedSpan>,
+}
+
+#[test]
+fn emits_span_events() {
+    let layer = tracing_stackdriver::layer().with_span_events(
+        tracing_stackdriver::SpanEvents::OPEN | tracing_stackdriver::SpanEvents::CLOSE,
+    );
+
+    let events = run_with_tracing_layer::<MockSpanEvent>(layer, || {
+        let span = tracing::info_span!("request");
+        let _guard = span.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let messages: Vec<_> = events.iter().map(|event| event.message.as_str()).collect();
+    assert_eq!(messages, ["span opened", "hello!", "span closed"]);
+
+    for event in [&events[0], &events[2]] {
+        assert_eq!(event.severity, "DEBUG");
+        assert_eq!(
+            event.span.as_ref().map(|span| span.name.as_str()),
+            Some("request")
+        );
+    }
+}
+
+#[test]
+fn emits_span_opened_events_when_spans_are_first_entered() {
+    let layer =
+        tracing_stackdriver::layer().with_span_events(tracing_stackdriver::SpanEvents::OPEN);
+
+    let events = run_with_tracing_layer::<MockSpanEvent>(layer, || {
+        let span = tracing::info_span!("request");
+        tracing::info!("created");
+
+        for _ in 0..2 {
+            let _guard = span.enter();
+            tracing::info!("entered");
+        }
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let messages: Vec<_> = events.iter().map(|event| event.message.as_str()).collect();
+    assert_eq!(messages, ["created", "span opened", "entered", "entered"]);
+}
+
+#[test]
+fn emits_selected_span_events() {
+    let layer =
+        tracing_stackdriver::layer().with_span_events(tracing_stackdriver::SpanEvents::CLOSE);
+
+    let events = run_with_tracing_layer::<MockSpanEvent>(layer, || {
+        let _span = tracing::info_span!("request");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let messages: Vec<_> = events.iter().map(|event| event.message.as_str()).collect();
+    assert_eq!(messages, ["span closed"]);
+}
+
+#[test]
+fn keeps_span_fields_named_like_span_names() {
+    let events = run_with_tracing_layer::<MockEventWithSpans>(tracing_stackdriver::layer(), || {
+        let span = tracing::info_span!("request", name = "checkout");
+        let _guard = span.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.span.name, "request");
+    assert_eq!(event.spans[0]["name"], "request");
+    assert_eq!(event.spans[0]["nameValue"], "checkout");
+}
+
+#[test]
+fn writes_span_names_to_custom_keys() {
+    let layer = tracing_stackdriver::layer().with_span_name_key("span_name");
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        let span = tracing::info_span!("request", name = "checkout");
+        let _guard = span.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["span"]["span_name"], "request");
+    assert_eq!(event["span"]["name"], "checkout");
+    assert_eq!(event["spans"][0]["span_name"], "request");
+    assert_eq!(event["spans"][0]["name"], "checkout");
+}
@@ -0,0 +1,57 @@
+use helpers::run_with_tracing_layer;
+
+mod helpers;
+
+type MockEvent = serde_json::Map<String, serde_json::Value>;
+
+#[test]
+fn includes_target_by_default() {
+    let events = run_with_tracing_layer::<MockEvent>(
+        tracing_stackdriver::layer(),
+        || tracing::info!(target: "my_target", "hello!"),
+    )
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["target"], "my_target");
+}
+
+#[test]
+fn excludes_target() {
+    let layer = tracing_stackdriver::layer().with_target(false);
+
+    let events = run_with_tracing_layer::<MockEvent>(layer, || tracing::info!("hello!"))
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert!(!event.contains_key("target"));
+}
+
+#[test]
+fn renames_target() {
+    let layer = tracing_stackdriver::layer().with_target_key("logger");
+
+    let events = run_with_tracing_layer::<MockEvent>(
+        layer,
+        || tracing::info!(target: "my_target", "hello!"),
+    )
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["logger"], "my_target");
+    assert!(!event.contains_key("target"));
+}
+
+#[test]
+fn excludes_renamed_target() {
+    let layer = tracing_stackdriver::layer()
+        .with_target_key("logger")
+        .with_target(false);
+
+    let events = run_with_tracing_layer::<MockEvent>(layer, || tracing::info!("hello!"))
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert!(!event.contains_key("logger"));
+    assert!(!event.contains_key("target"));
+}
@@ -0,0 +1,88 @@
+use helpers::run_with_tracing_layer;
+
+mod helpers;
+
+type MockEvent = serde_json::Map<String, serde_json::Value>;
+
+#[test]
+fn excludes_threads_by_default() {
+    let events = run_with_tracing_layer::<MockEvent>(tracing_stackdriver::layer(), || {
+        tracing::info!("hello!")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert!(!event.contains_key("thread"));
+}
+
+#[test]
+fn includes_thread_names_and_ids() {
+    let layer = tracing_stackdriver::layer()
+        .with_thread_names(true)
+        .with_thread_ids(true);
+
+    let mut thread_id = None;
+
+    let events = run_with_tracing_layer::<MockEvent>(layer, || {
+        let dispatch = tracing::dispatcher::get_default(Clone::clone);
+
+        let id = std::thread::Builder::new()
+            .name("worker".into())
+            .spawn(move || {
+                tracing::dispatcher::with_default(&dispatch, || tracing::info!("hello!"));
+                std::thread::current().id()
+            })
+            .expect("Error spawning thread")
+            .join()
+            .expect("Error joining thread");
+
+        thread_id = Some(format!("{:?}", id));
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["thread"]["name"], "worker");
+    assert_eq!(event["thread"]["id"].as_str(), thread_id.as_deref());
+}
+
+#[test]
+fn omits_names_of_unnamed_threads() {
+    let layer = tracing_stackdriver::layer().with_thread_names(true);
+
+    let events = run_with_tracing_layer::<MockEvent>(layer, || {
+        let dispatch = tracing::dispatcher::get_default(Clone::clone);
+
+        std::thread::spawn(move || {
+            tracing::dispatcher::with_default(&dispatch, || tracing::info!("hello!"))
+        })
+        .join()
+        .expect("Error joining thread");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert!(!event.contains_key("thread"));
+}
+
+#[test]
+fn includes_thread_ids_of_unnamed_threads() {
+    let layer = tracing_stackdriver::layer()
+        .with_thread_names(true)
+        .with_thread_ids(true);
+
+    let events = run_with_tracing_layer::<MockEvent>(layer, || {
+        let dispatch = tracing::dispatcher::get_default(Clone::clone);
+
+        std::thread::spawn(move || {
+            tracing::dispatcher::with_default(&dispatch, || tracing::info!("hello!"))
+        })
+        .join()
+        .expect("Error joining thread");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    let thread = event["thread"].as_object().expect("No thread found");
+    assert!(!thread.contains_key("name"));
+    assert!(thread["id"].is_string());
+}
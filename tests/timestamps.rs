@@ -0,0 +1,222 @@
+use helpers::run_with_tracing_layer;
+use mocks::MockDefaultEvent;
+use serde::Deserialize;
+use time::OffsetDateTime;
+use tracing_stackdriver::{Clock, TimestampStyle};
+
+mod helpers;
+mod mocks;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MockEventWithSplitTimestamp {
+    timestamp_seconds: i64,
+    timestamp_nanos: u32,
+    time: Option<String>,
+}
+
+#[test]
+fn includes_rfc3339_timestamps() {
+    let start = OffsetDateTime::now_utc();
+    let layer = tracing_stackdriver::layer().with_timestamp_style(TimestampStyle::Rfc3339);
+
+    let events = run_with_tracing_layer::<MockDefaultEvent>(layer, || tracing::info!("hello!"))
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert!(event.time > start);
+}
+
+#[test]
+fn includes_split_timestamps() {
+    let start = OffsetDateTime::now_utc();
+    let layer = tracing_stackdriver::layer().with_timestamp_style(TimestampStyle::SecondsNanos);
+
+    let events =
+        run_with_tracing_layer::<MockEventWithSplitTimestamp>(layer, || tracing::info!("hello!"))
+            .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    let time = OffsetDateTime::from_unix_timestamp(event.timestamp_seconds)
+        .expect("Invalid timestampSeconds")
+        .replace_nanosecond(event.timestamp_nanos)
+        .expect("Invalid timestampNanos");
+
+    assert!(time > start);
+    assert!(event.time.is_none());
+}
+
+#[derive(Debug, Deserialize)]
+struct MockTimestamp {
+    seconds: i64,
+    nanos: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithStructuredTimestamp {
+    timestamp: MockTimestamp,
+    time: Option<String>,
+}
+
+#[test]
+fn includes_structured_timestamps() {
+    let start = OffsetDateTime::now_utc();
+    let layer = tracing_stackdriver::layer().with_timestamp_style(TimestampStyle::Structured);
+
+    let events = run_with_tracing_layer::<MockEventWithStructuredTimestamp>(layer, || {
+        tracing::info!("hello!")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    let time = OffsetDateTime::from_unix_timestamp(event.timestamp.seconds)
+        .expect("Invalid seconds")
+        .replace_nanosecond(event.timestamp.nanos)
+        .expect("Invalid nanos");
+
+    assert!(time > start);
+    assert!(event.time.is_none());
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithCustomTimeKey {
+    #[serde(with = "time::serde::rfc3339")]
+    logged_at: OffsetDateTime,
+}
+
+#[test]
+fn includes_rfc3339_timestamps_under_custom_keys() {
+    let start = OffsetDateTime::now_utc();
+    let layer = tracing_stackdriver::layer().with_time_key("logged_at");
+
+    let events =
+        run_with_tracing_layer::<MockEventWithCustomTimeKey>(layer, || tracing::info!("hello!"))
+            .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert!(event.logged_at > start);
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithRfc3339Timestamp {
+    #[serde(deserialize_with = "time::serde::rfc3339::deserialize")]
+    timestamp: OffsetDateTime,
+    time: Option<String>,
+}
+
+#[test]
+fn includes_rfc3339_timestamps_under_timestamp_key() {
+    let start = OffsetDateTime::now_utc();
+    let layer = tracing_stackdriver::layer().with_time_key("timestamp");
+
+    let events =
+        run_with_tracing_layer::<MockEventWithRfc3339Timestamp>(layer, || tracing::info!("hello!"))
+            .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert!(event.timestamp > start);
+    assert!(event.time.is_none());
+}
+
+struct FixedClock;
+
+impl Clock for FixedClock {
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp_nanos(1_682_942_400_123_456_789)
+            .expect("Invalid fixed time")
+    }
+}
+
+#[test]
+fn uses_custom_clocks() {
+    let layer = tracing_stackdriver::layer().with_clock(FixedClock);
+
+    let events =
+        run_with_tracing_layer::<serde_json::Map<String, serde_json::Value>>(layer, || {
+            tracing::info!("hello!")
+        })
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["time"], "2023-05-01T12:00:00.123456789Z");
+}
+
+#[test]
+fn uses_custom_time_formatters() {
+    let layer = tracing_stackdriver::layer()
+        .with_clock(FixedClock)
+        .with_time_formatter(|now: OffsetDateTime, time: &mut String| {
+            time.push_str(&format!(
+                "{:02}/{:02}/{} {:02}:{:02}",
+                u8::from(now.month()),
+                now.day(),
+                now.year(),
+                now.hour(),
+                now.minute()
+            ))
+        });
+
+    let events =
+        run_with_tracing_layer::<serde_json::Map<String, serde_json::Value>>(layer, || {
+            tracing::info!("hello!")
+        })
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["time"], "05/01/2023 12:00");
+}
+
+#[test]
+fn escapes_custom_time_formatters() {
+    let layer = tracing_stackdriver::layer()
+        .with_time_formatter(|_, time: &mut String| time.push_str("\"quoted\"\n\\"));
+
+    let events =
+        run_with_tracing_layer::<serde_json::Map<String, serde_json::Value>>(layer, || {
+            tracing::info!("hello!")
+        })
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["time"], "\"quoted\"\n\\");
+    assert_eq!(event["message"], "hello!");
+}
+
+#[test]
+fn uses_closure_clocks() {
+    let layer = tracing_stackdriver::layer().with_clock(|| OffsetDateTime::UNIX_EPOCH);
+
+    let events =
+        run_with_tracing_layer::<serde_json::Map<String, serde_json::Value>>(layer, || {
+            tracing::info!("hello!")
+        })
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["time"], "1970-01-01T00:00:00.000000000Z");
+}
+
+#[test]
+fn writes_deterministic_entries_with_fixed_clocks() {
+    let layer = tracing_stackdriver::layer()
+        .with_clock(FixedClock)
+        .with_source_location(false);
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::info_span!("request", id = 1).in_scope(|| tracing::warn!(answer = 42, "hello!"))
+    })
+    .expect("Error converting test buffer to JSON");
+
+    assert_eq!(
+        events,
+        [serde_json::json!({
+            "time": "2023-05-01T12:00:00.123456789Z",
+            "target": "timestamps",
+            "span": { "id": 1, "name": "request" },
+            "spans": [{ "id": 1, "name": "request" }],
+            "severity": "WARNING",
+            "message": "hello!",
+            "answer": 42,
+        })]
+    );
+}
@@ -0,0 +1,176 @@
+#![cfg(feature = "tower")]
+use helpers::run_with_tracing_layer;
+use serde::Deserialize;
+use std::convert::Infallible;
+use tower::{service_fn, Layer, ServiceExt};
+use tracing_stackdriver::{HttpLayer, TraceContextLayer};
+
+mod helpers;
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MockHttpRequest {
+    request_method: String,
+    request_url: String,
+    status: u16,
+    latency: String,
+    protocol: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MockRequestEvent {
+    #[serde(rename = "logging.googleapis.com/trace")]
+    trace: String,
+    #[serde(rename = "logging.googleapis.com/spanId")]
+    span_id: String,
+    #[serde(rename = "logging.googleapis.com/trace_sampled")]
+    trace_sampled: bool,
+    http_request: MockHttpRequest,
+}
+
+fn handle(request: http::Request<()>) -> Result<http::Response<()>, Infallible> {
+    let status = match request.uri().path() {
+        "/missing" => http::StatusCode::NOT_FOUND,
+        _ => http::StatusCode::OK,
+    };
+
+    Ok(http::Response::builder()
+        .status(status)
+        .header(http::header::CONTENT_LENGTH, "2")
+        .body(())
+        .expect("Error building response"))
+}
+
+fn send(request: http::Request<()>) {
+    let service =
+        TraceContextLayer::new().layer(service_fn(|request| async move { handle(request) }));
+
+    futures_executor::block_on(service.oneshot(request)).expect("Error handling request");
+}
+
+#[test]
+fn correlates_requests_with_traceparent_headers() {
+    let layer = tracing_stackdriver::layer().with_project_id("my-proj");
+
+    let events = run_with_tracing_layer::<MockRequestEvent>(layer, || {
+        send(
+            http::Request::get("/missing?page=2")
+                .header(http::header::HOST, "example.com")
+                .header("traceparent", format!("00-{TRACE_ID}-000000000000004a-01"))
+                .body(())
+                .expect("Error building request"),
+        )
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.trace, format!("projects/my-proj/traces/{TRACE_ID}"));
+    assert_eq!(event.span_id, "000000000000004a");
+    assert!(event.trace_sampled);
+    assert_eq!(event.http_request.request_method, "GET");
+    assert_eq!(
+        event.http_request.request_url,
+        "http://example.com/missing?page=2"
+    );
+    assert_eq!(event.http_request.status, 404);
+    assert!(event.http_request.latency.ends_with('s'));
+    assert_eq!(event.http_request.protocol, "HTTP/1.1");
+}
+
+#[test]
+fn correlates_requests_with_cloud_trace_headers() {
+    let layer = tracing_stackdriver::layer().with_project_id("my-proj");
+
+    let events = run_with_tracing_layer::<MockRequestEvent>(layer, || {
+        send(
+            http::Request::post("https://example.com/")
+                .header("x-cloud-trace-context", format!("{TRACE_ID}/74;o=1"))
+                .body(())
+                .expect("Error building request"),
+        )
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.trace, format!("projects/my-proj/traces/{TRACE_ID}"));
+    assert_eq!(event.span_id, "000000000000004a");
+    assert_eq!(event.http_request.request_method, "POST");
+    assert_eq!(event.http_request.request_url, "https://example.com/");
+    assert_eq!(event.http_request.status, 200);
+}
+
+#[test]
+fn records_request_and_response_sizes() {
+    let events = run_with_tracing_layer::<serde_json::Value>(tracing_stackdriver::layer(), || {
+        send(
+            http::Request::post("https://example.com/orders")
+                .header(http::header::CONTENT_LENGTH, "42")
+                .header(http::header::USER_AGENT, "curl/8.0.1")
+                .body(())
+                .expect("Error building request"),
+        )
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["httpRequest"]["requestSize"], "42");
+    assert_eq!(event["httpRequest"]["responseSize"], "2");
+    assert_eq!(event["httpRequest"]["userAgent"], "curl/8.0.1");
+}
+
+#[test]
+fn records_failed_requests() {
+    let service = HttpLayer::new().layer(service_fn(|_: http::Request<()>| async move {
+        Err::<http::Response<()>, _>("connection reset")
+    }));
+
+    let events = run_with_tracing_layer::<serde_json::Value>(tracing_stackdriver::layer(), || {
+        let request = http::Request::get("https://example.com/")
+            .body(())
+            .expect("Error building request");
+
+        futures_executor::block_on(service.oneshot(request)).expect_err("Request succeeded");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["severity"], "ERROR");
+    assert_eq!(event["message"], "request failed");
+    assert_eq!(event["httpRequest"]["requestMethod"], "GET");
+    assert_eq!(event["httpRequest"]["requestUrl"], "https://example.com/");
+    assert!(event["httpRequest"]["latency"]
+        .as_str()
+        .expect("No latency found")
+        .ends_with('s'));
+    assert!(event["httpRequest"].get("status").is_none());
+}
+
+#[test]
+fn includes_synchronous_work_in_latencies() {
+    let service = HttpLayer::new().layer(service_fn(|request| {
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        async move { handle(request) }
+    }));
+
+    let events = run_with_tracing_layer::<serde_json::Value>(tracing_stackdriver::layer(), || {
+        let request = http::Request::get("https://example.com/")
+            .body(())
+            .expect("Error building request");
+
+        futures_executor::block_on(service.oneshot(request)).expect("Error handling request");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    let latency: f64 = event["httpRequest"]["latency"]
+        .as_str()
+        .and_then(|latency| latency.strip_suffix('s'))
+        .expect("No latency found")
+        .parse()
+        .expect("Invalid latency");
+
+    assert!(latency >= 0.02, "{latency}s is too short");
+}
@@ -0,0 +1,322 @@
+use helpers::run_with_tracing_layer;
+use serde::Deserialize;
+use tracing_stackdriver::{parse_cloud_trace_context, parse_traceparent, ParseError, TraceContext};
+
+mod helpers;
+
+static TRACE_ID: &str = "105445aa7843bc8bf206b12000100000";
+
+#[test]
+fn parses_cloud_trace_headers() {
+    let trace_context = TraceContext::from_cloud_trace_header(&format!("{TRACE_ID}/1;o=1"))
+        .expect("Error parsing header");
+
+    assert_eq!(
+        trace_context,
+        TraceContext {
+            trace_id: TRACE_ID.to_owned(),
+            span_id: Some(1),
+            sampled: true,
+        }
+    );
+}
+
+#[test]
+fn parses_cloud_trace_headers_without_options_or_span_ids() {
+    let without_options =
+        TraceContext::from_cloud_trace_header(&format!("{TRACE_ID}/74")).expect("Error parsing");
+    let without_span_id =
+        TraceContext::from_cloud_trace_header(TRACE_ID).expect("Error parsing header");
+
+    assert_eq!(without_options.span_id, Some(74));
+    assert!(!without_options.sampled);
+    assert_eq!(without_span_id.span_id, None);
+    assert!(!without_span_id.sampled);
+}
+
+#[test]
+fn rejects_malformed_cloud_trace_headers() {
+    let parse = TraceContext::from_cloud_trace_header;
+
+    assert_eq!(parse(""), Err(ParseError::InvalidTraceId));
+    assert_eq!(parse("not-a-trace/1;o=1"), Err(ParseError::InvalidTraceId));
+    assert_eq!(
+        parse(&format!("{TRACE_ID}/abc;o=1")),
+        Err(ParseError::InvalidSpanId)
+    );
+    assert_eq!(
+        parse(&format!("{TRACE_ID}/1;o=yes")),
+        Err(ParseError::InvalidOptions)
+    );
+}
+
+#[test]
+fn parses_cloud_trace_contexts() {
+    assert_eq!(
+        parse_cloud_trace_context(&format!("{TRACE_ID}/74;o=1")),
+        Some(TraceContext {
+            trace_id: TRACE_ID.to_owned(),
+            span_id: Some(74),
+            sampled: true,
+        })
+    );
+}
+
+#[test]
+fn parses_cloud_trace_contexts_without_sampled_flags() {
+    assert_eq!(
+        parse_cloud_trace_context(&format!("{TRACE_ID}/74")),
+        Some(TraceContext {
+            trace_id: TRACE_ID.to_owned(),
+            span_id: Some(74),
+            sampled: false,
+        })
+    );
+    assert_eq!(
+        parse_cloud_trace_context(&format!("{TRACE_ID};o=1")).map(|context| context.span_id),
+        Some(None)
+    );
+}
+
+#[test]
+fn skips_malformed_cloud_trace_contexts() {
+    assert_eq!(parse_cloud_trace_context("not-a-trace/74;o=1"), None);
+    assert_eq!(
+        parse_cloud_trace_context(&format!("{TRACE_ID}/-1;o=1")),
+        None
+    );
+}
+
+#[derive(Debug, Deserialize)]
+struct MockEventWithTraceContext {
+    #[serde(rename = "logging.googleapis.com/trace")]
+    trace: String,
+    #[serde(rename = "logging.googleapis.com/spanId")]
+    span_id: String,
+    #[serde(rename = "logging.googleapis.com/trace_sampled")]
+    trace_sampled: bool,
+}
+
+#[test]
+fn records_trace_context_onto_spans() {
+    let trace_context = TraceContext::from_cloud_trace_header(&format!("{TRACE_ID}/74;o=1"))
+        .expect("Error parsing header");
+    let layer = tracing_stackdriver::layer().with_project_id("my-proj");
+
+    let events = run_with_tracing_layer::<MockEventWithTraceContext>(layer, || {
+        let span = tracing::info_span!(
+            "request",
+            trace_id = tracing::field::Empty,
+            span_id = tracing::field::Empty,
+            trace_sampled = tracing::field::Empty,
+        );
+        trace_context.record(&span);
+        let _guard = span.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.trace, format!("projects/my-proj/traces/{TRACE_ID}"));
+    assert_eq!(event.span_id, "000000000000004a");
+    assert!(event.trace_sampled);
+}
+
+#[test]
+fn parses_traceparent_headers() {
+    let sampled = TraceContext::from_traceparent(&format!("00-{TRACE_ID}-000000000000004a-01"))
+        .expect("Error parsing sampled header");
+    let unsampled = TraceContext::from_traceparent(&format!("00-{TRACE_ID}-000000000000004a-00"))
+        .expect("Error parsing unsampled header");
+
+    assert_eq!(
+        sampled,
+        TraceContext {
+            trace_id: TRACE_ID.to_owned(),
+            span_id: Some(74),
+            sampled: true,
+        }
+    );
+    assert!(!unsampled.sampled);
+}
+
+#[test]
+fn parses_traceparent_headers_from_future_versions() {
+    let trace_context =
+        TraceContext::from_traceparent(&format!("01-{TRACE_ID}-000000000000004a-03-extra"))
+            .expect("Error parsing header");
+
+    assert!(trace_context.sampled);
+}
+
+#[test]
+fn rejects_malformed_traceparent_headers() {
+    let parse = TraceContext::from_traceparent;
+    let span_id = "000000000000004a";
+
+    assert_eq!(
+        parse(&format!("ff-{TRACE_ID}-{span_id}-01")),
+        Err(ParseError::InvalidVersion)
+    );
+    assert_eq!(
+        parse(&format!("00-{TRACE_ID}-{span_id}-01-extra")),
+        Err(ParseError::InvalidVersion)
+    );
+    assert_eq!(
+        parse(&format!("00-{}-{span_id}-01", "0".repeat(32))),
+        Err(ParseError::InvalidTraceId)
+    );
+    assert_eq!(
+        parse(&format!("00-abc123-{span_id}-01")),
+        Err(ParseError::InvalidTraceId)
+    );
+    assert_eq!(
+        parse(&format!("00-{TRACE_ID}-{}-01", "0".repeat(16))),
+        Err(ParseError::InvalidSpanId)
+    );
+    assert_eq!(
+        parse(&format!("00-{TRACE_ID}-{span_id}")),
+        Err(ParseError::InvalidOptions)
+    );
+}
+
+#[test]
+fn parses_sampled_traceparents() {
+    assert_eq!(
+        parse_traceparent(&format!("00-{TRACE_ID}-000000000000004a-01")),
+        Some(TraceContext {
+            trace_id: TRACE_ID.to_owned(),
+            span_id: Some(74),
+            sampled: true,
+        })
+    );
+}
+
+#[test]
+fn parses_unsampled_traceparents() {
+    assert_eq!(
+        parse_traceparent(&format!("00-{TRACE_ID}-000000000000004a-00")),
+        Some(TraceContext {
+            trace_id: TRACE_ID.to_owned(),
+            span_id: Some(74),
+            sampled: false,
+        })
+    );
+}
+
+#[test]
+fn skips_malformed_traceparents() {
+    assert_eq!(
+        parse_traceparent(&format!("ff-{TRACE_ID}-000000000000004a-01")),
+        None
+    );
+    assert_eq!(parse_traceparent(&format!("00-{TRACE_ID}-4a-01")), None);
+}
+
+#[test]
+fn correlates_events_with_parsed_traceparents() {
+    let trace_context = parse_traceparent(&format!("00-{TRACE_ID}-000000000000004a-01"))
+        .expect("Error parsing header");
+    let layer = tracing_stackdriver::layer().with_project_id("my-proj");
+
+    let events = run_with_tracing_layer::<MockEventWithTraceContext>(layer, || {
+        let request = tracing::info_span!("request");
+        trace_context.attach(&request);
+        let _request = request.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.trace, format!("projects/my-proj/traces/{TRACE_ID}"));
+    assert_eq!(event.span_id, "000000000000004a");
+    assert!(event.trace_sampled);
+}
+
+#[test]
+fn attaches_trace_context_to_spans() {
+    let trace_context = TraceContext {
+        trace_id: TRACE_ID.to_owned(),
+        span_id: Some(74),
+        sampled: true,
+    };
+    let layer = tracing_stackdriver::layer().with_project_id("my-proj");
+
+    let events = run_with_tracing_layer::<MockEventWithTraceContext>(layer, || {
+        let request = tracing::info_span!("request");
+        trace_context.attach(&request);
+        let _request = request.enter();
+        let db_query = tracing::info_span!("db_query");
+        let _db_query = db_query.enter();
+        tracing::info!("hello!");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.trace, format!("projects/my-proj/traces/{TRACE_ID}"));
+    assert_eq!(event.span_id, "000000000000004a");
+    assert!(event.trace_sampled);
+}
+
+#[test]
+fn prefers_attached_trace_context_over_span_fields() {
+    let trace_context = TraceContext {
+        trace_id: TRACE_ID.to_owned(),
+        span_id: Some(74),
+        sampled: true,
+    };
+
+    let events =
+        run_with_tracing_layer::<MockEventWithTraceContext>(tracing_stackdriver::layer(), || {
+            let span = tracing::info_span!("request", trace_id = "span-trace", span_id = "1");
+            trace_context.attach(&span);
+            let _guard = span.enter();
+            tracing::info!("hello!");
+        })
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.trace, TRACE_ID);
+    assert_eq!(event.span_id, "000000000000004a");
+}
+
+#[test]
+fn prefers_closer_span_fields_over_ancestor_trace_contexts() {
+    let trace_context = TraceContext {
+        trace_id: TRACE_ID.to_owned(),
+        span_id: Some(74),
+        sampled: false,
+    };
+
+    let events =
+        run_with_tracing_layer::<MockEventWithTraceContext>(tracing_stackdriver::layer(), || {
+            let request = tracing::info_span!("request");
+            trace_context.attach(&request);
+            let _request = request.enter();
+            let retry = tracing::info_span!("retry", span_id = "4b", trace_sampled = true);
+            let _retry = retry.enter();
+            tracing::info!("hello!");
+        })
+        .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.trace, TRACE_ID);
+    assert_eq!(event.span_id, "000000000000004b");
+    assert!(event.trace_sampled);
+}
+
+#[test]
+fn ignores_trace_context_without_stackdriver_layer() {
+    let trace_context = TraceContext {
+        trace_id: TRACE_ID.to_owned(),
+        span_id: None,
+        sampled: false,
+    };
+
+    let subscriber = tracing_subscriber::registry();
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("request");
+        trace_context.attach(&span);
+    });
+}
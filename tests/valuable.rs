@@ -1,6 +1,6 @@
 #![allow(clippy::disallowed_names)]
 #![cfg(all(tracing_unstable, feature = "valuable"))]
-use helpers::run_with_tracing;
+use helpers::{run_with_tracing, run_with_tracing_layer};
 use mocks::{MockDefaultEvent, MockHttpEvent};
 use serde::Deserialize;
 use std::fmt::Debug;
@@ -24,6 +24,18 @@ fn handles_valuable_severity_override() {
     assert_eq!(event.severity, "NOTICE");
 }
 
+#[test]
+fn handles_extended_valuable_severity_overrides() {
+    let events = run_with_tracing::<MockDefaultEvent>(|| {
+        tracing::error!(severity = LogSeverity::Critical.as_value(), "critical");
+        tracing::error!(severity = LogSeverity::Emergency.as_value(), "emergency");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let emitted: Vec<_> = events.iter().map(|event| event.severity.as_str()).collect();
+    assert_eq!(emitted, ["CRITICAL", "EMERGENCY"]);
+}
+
 #[test]
 fn validates_structured_http_requests() {
     let request_method = http::Method::GET;
@@ -33,10 +45,20 @@ fn validates_structured_http_requests() {
 
     let http_request = tracing_stackdriver::HttpRequest {
         request_method: Some(request_method.clone()),
+        request_url: Some("https://example.com/search?q=1".parse().unwrap()),
+        request_size: Some(42),
+        response_size: Some(5_000_000_000),
         latency: Some(latency),
         status: Some(status),
+        user_agent: Some("curl/8.0".to_owned()),
         remote_ip: Some(remote_ip),
-        ..Default::default()
+        server_ip: Some(std::net::IpAddr::from([10, 0, 0, 1])),
+        referer: Some("https://example.com/".parse().unwrap()),
+        cache_lookup: Some(true),
+        cache_hit: Some(false),
+        cache_validated_with_origin_server: Some(true),
+        cache_fill_bytes: Some(1024),
+        protocol: Some("HTTP/2".to_owned()),
     };
 
     let events = run_with_tracing::<MockHttpEvent>(|| {
@@ -52,12 +74,39 @@ fn validates_structured_http_requests() {
         event.http_request.request_method,
         request_method.to_string()
     );
-    assert_eq!(
-        event.http_request.latency,
-        format!("{}s", latency.as_secs_f32())
-    );
+    assert_eq!(event.http_request.latency, "1.234000000s");
     assert_eq!(event.http_request.status, status.as_u16());
     assert_eq!(event.http_request.remote_ip, remote_ip.to_string());
+
+    let events = run_with_tracing::<serde_json::Value>(|| {
+        tracing::info!(
+            http_request = http_request.as_value(),
+            "http_request testing"
+        )
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(
+        event["httpRequest"],
+        serde_json::json!({
+            "requestMethod": "GET",
+            "requestUrl": "https://example.com/search?q=1",
+            "requestSize": "42",
+            "responseSize": "5000000000",
+            "status": 200,
+            "userAgent": "curl/8.0",
+            "remoteIp": "127.0.0.1",
+            "serverIp": "10.0.0.1",
+            "referer": "https://example.com/",
+            "latency": "1.234000000s",
+            "cacheLookup": true,
+            "cacheHit": false,
+            "cacheValidatedWithOriginServer": true,
+            "cacheFillBytes": "1024",
+            "protocol": "HTTP/2",
+        })
+    );
 }
 
 #[derive(Debug, Deserialize, Valuable, PartialEq)]
@@ -72,6 +121,64 @@ struct MockStructuredEvent {
     structured_log: StructuredLog,
 }
 
+fn serialize_latency(latency: std::time::Duration) -> String {
+    let http_request = tracing_stackdriver::HttpRequest {
+        latency: Some(latency),
+        ..Default::default()
+    };
+
+    let events = run_with_tracing::<serde_json::Value>(|| {
+        tracing::info!(http_request = http_request.as_value(), "latency testing")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    events.first().expect("No event heard")["httpRequest"]["latency"]
+        .as_str()
+        .expect("No latency serialized")
+        .to_owned()
+}
+
+#[test]
+fn serializes_sub_second_latency() {
+    assert_eq!(
+        serialize_latency(std::time::Duration::from_nanos(230_000_001)),
+        "0.230000001s"
+    );
+    assert_eq!(
+        serialize_latency(std::time::Duration::from_micros(1)),
+        "0.000001000s"
+    );
+}
+
+#[test]
+fn serializes_zero_latency() {
+    assert_eq!(serialize_latency(std::time::Duration::ZERO), "0.000000000s");
+}
+
+#[test]
+fn serializes_multi_hour_latency() {
+    assert_eq!(
+        serialize_latency(std::time::Duration::new(3 * 60 * 60, 500)),
+        "10800.000000500s"
+    );
+}
+
+#[test]
+fn serializes_multi_second_latency() {
+    assert_eq!(
+        serialize_latency(std::time::Duration::new(86_400, 5)),
+        "86400.000000005s"
+    );
+    assert_eq!(
+        serialize_latency(std::time::Duration::from_secs(3)),
+        "3.000000000s"
+    );
+    assert_eq!(
+        serialize_latency(std::time::Duration::new(3, 500)),
+        "3.000000500s"
+    );
+}
+
 #[test]
 fn includes_valuable_structures() {
     let foo = "testing".to_string();
@@ -90,3 +197,319 @@ fn includes_valuable_structures() {
     let event = events.first().expect("No event heard");
     assert_eq!(event.structured_log, structured_log);
 }
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MockBuiltHttpRequest {
+    request_url: String,
+    user_agent: String,
+    cache_hit: bool,
+    status: Option<u16>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MockBuiltHttpEvent {
+    http_request: MockBuiltHttpRequest,
+}
+
+#[test]
+fn builds_structured_http_requests() {
+    let request_url: url::Url = "https://example.com/path?query=1".parse().unwrap();
+
+    let http_request = tracing_stackdriver::HttpRequest::builder()
+        .request_url(request_url.clone())
+        .user_agent("curl/8.0.1")
+        .cache_hit(true)
+        .build();
+
+    let events = run_with_tracing::<MockBuiltHttpEvent>(|| {
+        tracing::info!(
+            http_request = http_request.as_value(),
+            "http_request testing"
+        )
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event.http_request.request_url, request_url.to_string());
+    assert_eq!(event.http_request.user_agent, "curl/8.0.1");
+    assert!(event.http_request.cache_hit);
+    assert_eq!(event.http_request.status, None);
+}
+
+#[test]
+fn converts_http_parts_to_http_requests() {
+    let (request, _) = http::Request::post("/path?query=1")
+        .version(http::Version::HTTP_2)
+        .header("host", "example.com")
+        .header("USER-AGENT", "curl/8.0.1")
+        .header("Referer", "https://example.com/")
+        .header("content-length", "42")
+        .body(())
+        .unwrap()
+        .into_parts();
+
+    let (response, _) = http::Response::builder()
+        .status(http::StatusCode::CREATED)
+        .header("content-length", "7")
+        .body(())
+        .unwrap()
+        .into_parts();
+
+    let http_request = tracing_stackdriver::HttpRequest::from_parts(&request, Some(&response));
+
+    assert_eq!(http_request.request_method, Some(http::Method::POST));
+    assert_eq!(
+        http_request.request_url.map(String::from).as_deref(),
+        Some("http://example.com/path?query=1")
+    );
+    assert_eq!(http_request.protocol.as_deref(), Some("HTTP/2.0"));
+    assert_eq!(http_request.user_agent.as_deref(), Some("curl/8.0.1"));
+    assert_eq!(
+        http_request.referer.map(String::from).as_deref(),
+        Some("https://example.com/")
+    );
+    assert_eq!(http_request.request_size, Some(42));
+    assert_eq!(http_request.response_size, Some(7));
+    assert_eq!(http_request.status, Some(http::StatusCode::CREATED));
+}
+
+#[test]
+fn converts_http_requests_to_http_requests() {
+    let request = http::Request::get("https://example.com/search?q=1")
+        .version(http::Version::HTTP_11)
+        .header("user-agent", "curl/8.0.1")
+        .header("referer", &b"\xffinvalid"[..])
+        .header("content-length", "3")
+        .body("abc")
+        .unwrap();
+
+    let http_request = tracing_stackdriver::HttpRequest::from(&request);
+
+    assert_eq!(http_request.request_method, Some(http::Method::GET));
+    assert_eq!(
+        http_request.request_url.map(String::from).as_deref(),
+        Some("https://example.com/search?q=1")
+    );
+    assert_eq!(http_request.protocol.as_deref(), Some("HTTP/1.1"));
+    assert_eq!(http_request.user_agent.as_deref(), Some("curl/8.0.1"));
+    assert_eq!(http_request.referer, None);
+    assert_eq!(http_request.request_size, Some(3));
+    assert_eq!(http_request.response_size, None);
+    assert_eq!(http_request.status, None);
+    assert_eq!(http_request.latency, None);
+}
+
+#[test]
+fn merges_http_responses_into_http_requests() {
+    let request = http::Request::post("https://example.com/orders")
+        .header("content-length", "42")
+        .body(())
+        .unwrap();
+    let response = http::Response::builder()
+        .status(http::StatusCode::CREATED)
+        .header("content-length", "7")
+        .body(())
+        .unwrap();
+
+    let mut http_request = tracing_stackdriver::HttpRequest::from(&request);
+    http_request.merge_response(&response, std::time::Duration::from_millis(25));
+
+    let events = run_with_tracing::<serde_json::Value>(|| {
+        tracing::info!(http_request = http_request.as_value(), "merged response")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(
+        event["httpRequest"],
+        serde_json::json!({
+            "requestMethod": "POST",
+            "requestUrl": "https://example.com/orders",
+            "requestSize": "42",
+            "responseSize": "7",
+            "status": 201,
+            "latency": "0.025000000s",
+            "protocol": "HTTP/1.1",
+        })
+    );
+}
+
+#[test]
+fn records_http_request_events_like_structured_http_requests() {
+    let request = http::Request::get("https://example.com/search?q=1")
+        .header("user-agent", "curl/8.0.1")
+        .header("referer", "https://example.com/")
+        .body(())
+        .unwrap();
+    let response = http::Response::builder()
+        .status(http::StatusCode::OK)
+        .header("content-length", "7")
+        .body(())
+        .unwrap();
+
+    let mut http_request = tracing_stackdriver::HttpRequest::from(&request);
+    http_request.merge_response(&response, std::time::Duration::new(1, 5));
+    http_request.server_ip = Some(std::net::IpAddr::from([10, 0, 0, 1]));
+    http_request.cache_lookup = Some(true);
+
+    let events = run_with_tracing::<serde_json::Value>(|| {
+        tracing::info!(http_request = http_request.as_value(), "structured");
+        tracing_stackdriver::http_request_event!(tracing::Level::INFO, http_request, "dotted");
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let [structured, dotted] = &events[..] else {
+        panic!("Expected two events, heard {}", events.len());
+    };
+    assert_eq!(structured["httpRequest"], dotted["httpRequest"]);
+    assert_eq!(dotted["httpRequest"]["latency"], "1.000000005s");
+}
+
+#[test]
+fn tolerates_missing_http_headers() {
+    let (request, _) = http::Request::get("/path")
+        .header("user-agent", &b"\xffinvalid"[..])
+        .body(())
+        .unwrap()
+        .into_parts();
+
+    let http_request = tracing_stackdriver::HttpRequest::from_parts(&request, None);
+
+    assert_eq!(http_request.request_method, Some(http::Method::GET));
+    assert_eq!(http_request.request_url, None);
+    assert_eq!(http_request.user_agent, None);
+    assert_eq!(http_request.status, None);
+}
+
+#[test]
+fn substitutes_placeholders_for_unserializable_values() {
+    // JSON object keys must be strings, so list keys can't be serialized
+    let unserializable = std::collections::BTreeMap::from([(vec![1_u8], 1_u8)]);
+
+    let events = run_with_tracing::<serde_json::Value>(|| {
+        tracing::error!(
+            unserializable = unserializable.as_value(),
+            "unserializable testing"
+        )
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["unserializable"], "<unserializable>");
+    assert_eq!(event["severity"], "ERROR");
+    assert_eq!(event["message"], "unserializable testing");
+}
+
+#[test]
+fn records_json_values_verbatim() {
+    let payload = serde_json::json!({
+        "order": {
+            "id": 42,
+            "items": ["book", "pen"],
+            "total": 12.5,
+            "gift": null,
+        },
+        "express": true,
+    });
+
+    let events = run_with_tracing::<serde_json::Value>(|| {
+        tracing::info!(
+            payload = tracing_stackdriver::JsonValue::new(&payload).as_value(),
+            "order placed"
+        )
+    })
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["payload"], payload);
+}
+
+#[test]
+fn includes_structured_http_requests_in_error_contexts() {
+    let http_request = tracing_stackdriver::HttpRequest::builder()
+        .request_method(http::Method::GET)
+        .status(http::StatusCode::NOT_FOUND)
+        .user_agent("curl/8.0")
+        .build();
+
+    let events = helpers::run_with_tracing_layer::<serde_json::Value>(
+        tracing_stackdriver::layer().with_report_errors(true),
+        || tracing::error!(http_request = http_request.as_value(), "not found"),
+    )
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(
+        event["context"]["httpRequest"],
+        serde_json::json!({
+            "method": "GET",
+            "responseStatusCode": 404,
+            "userAgent": "curl/8.0",
+        })
+    );
+}
+
+#[test]
+fn redacts_structured_field_values() {
+    let payload = serde_json::json!({ "token": "secret", "scopes": ["read"] });
+
+    let events = helpers::run_with_tracing_layer::<serde_json::Value>(
+        tracing_stackdriver::layer().with_redacted_fields(["auth.*"]),
+        || {
+            tracing::info!(
+                auth = tracing_stackdriver::JsonValue::new(&payload).as_value(),
+                "logged in"
+            )
+        },
+    )
+    .expect("Error converting test buffer to JSON");
+
+    let event = events.first().expect("No event heard");
+    assert_eq!(event["auth"], "[REDACTED]");
+}
+
+fn serialize_duration(
+    duration_format: Option<tracing_stackdriver::DurationFormat>,
+) -> serde_json::Value {
+    let layer = match duration_format {
+        Some(duration_format) => tracing_stackdriver::layer().with_duration_format(duration_format),
+        None => tracing_stackdriver::layer(),
+    };
+    let elapsed = tracing_stackdriver::DurationValue(std::time::Duration::from_micros(1_500_250));
+
+    let events = run_with_tracing_layer::<serde_json::Value>(layer, || {
+        tracing::info!(elapsed = elapsed.as_value(), "duration testing")
+    })
+    .expect("Error converting test buffer to JSON");
+
+    events.first().expect("No event heard")["elapsed"].clone()
+}
+
+#[test]
+fn serializes_durations_as_milliseconds_by_default() {
+    assert_eq!(serialize_duration(None), serde_json::json!(1500));
+}
+
+#[test]
+fn serializes_durations_in_configured_formats() {
+    use tracing_stackdriver::DurationFormat;
+
+    assert_eq!(
+        serialize_duration(Some(DurationFormat::Secs)),
+        serde_json::json!(1.50025)
+    );
+    assert_eq!(
+        serialize_duration(Some(DurationFormat::Millis)),
+        serde_json::json!(1500)
+    );
+    assert_eq!(
+        serialize_duration(Some(DurationFormat::Micros)),
+        serde_json::json!(1500250)
+    );
+    assert_eq!(
+        serialize_duration(Some(DurationFormat::ProtoString)),
+        "1.500250000s"
+    );
+}
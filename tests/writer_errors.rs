@@ -0,0 +1,121 @@
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+/// Writer that always fails with the same kind of error
+struct FailingWriter(io::ErrorKind);
+
+impl io::Write for FailingWriter {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        Err(io::Error::from(self.0))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn handled_errors(kind: io::ErrorKind) -> Vec<io::ErrorKind> {
+    let errors = Arc::new(Mutex::new(vec![]));
+    let shared_errors = errors.clone();
+
+    let stackdriver = tracing_stackdriver::layer()
+        .with_writer(move || FailingWriter(kind))
+        .on_error(move |error| {
+            shared_errors
+                .lock()
+                .expect("Couldn't get lock on handled errors")
+                .push(error.kind())
+        });
+
+    tracing::subscriber::with_default(Registry::default().with(stackdriver), || {
+        tracing::info!("first");
+        tracing::error!("second");
+    });
+
+    let errors = errors.lock().expect("Couldn't get lock on handled errors");
+    errors.clone()
+}
+
+#[test]
+fn handles_broken_pipes() {
+    assert_eq!(
+        handled_errors(io::ErrorKind::BrokenPipe),
+        [io::ErrorKind::BrokenPipe, io::ErrorKind::BrokenPipe]
+    );
+}
+
+#[test]
+fn handles_blocked_writers() {
+    assert_eq!(
+        handled_errors(io::ErrorKind::WouldBlock),
+        [io::ErrorKind::WouldBlock, io::ErrorKind::WouldBlock]
+    );
+}
+
+#[test]
+fn handles_errors_of_writers_set_after_handlers() {
+    let errors = Arc::new(Mutex::new(vec![]));
+    let shared_errors = errors.clone();
+
+    let stackdriver = tracing_stackdriver::layer()
+        .on_error(move |error| {
+            shared_errors
+                .lock()
+                .expect("Couldn't get lock on handled errors")
+                .push(error.kind())
+        })
+        .with_writer(|| FailingWriter(io::ErrorKind::BrokenPipe));
+
+    tracing::subscriber::with_default(Registry::default().with(stackdriver), || {
+        tracing::info!("dropped");
+    });
+
+    let errors = errors.lock().expect("Couldn't get lock on handled errors");
+    assert_eq!(*errors, [io::ErrorKind::BrokenPipe]);
+}
+
+#[test]
+fn survives_writer_errors_without_handlers() {
+    let stackdriver =
+        tracing_stackdriver::layer().with_writer(|| FailingWriter(io::ErrorKind::BrokenPipe));
+
+    tracing::subscriber::with_default(Registry::default().with(stackdriver), || {
+        tracing::info!("dropped");
+    });
+}
+
+#[test]
+fn keeps_writing_after_handled_errors() {
+    let written = Arc::new(AtomicUsize::new(0));
+    let shared_written = written.clone();
+    let errors = Arc::new(AtomicUsize::new(0));
+    let shared_errors = errors.clone();
+
+    let stackdriver = tracing_stackdriver::layer()
+        .with_writer(move || {
+            // every other writer fails
+            if shared_written.fetch_add(1, Ordering::SeqCst) & 1 == 0 {
+                Box::new(FailingWriter(io::ErrorKind::BrokenPipe)) as Box<dyn io::Write>
+            } else {
+                Box::new(io::sink())
+            }
+        })
+        .on_error(move |_| {
+            shared_errors.fetch_add(1, Ordering::SeqCst);
+        });
+
+    tracing::subscriber::with_default(Registry::default().with(stackdriver), || {
+        for _ in 0..4 {
+            tracing::info!("sometimes dropped");
+        }
+    });
+
+    assert_eq!(written.load(Ordering::SeqCst), 4);
+    assert_eq!(errors.load(Ordering::SeqCst), 2);
+}
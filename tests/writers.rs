@@ -0,0 +1,66 @@
+use helpers::MockWriter;
+use mocks::MockDefaultEvent;
+use std::sync::{Arc, Mutex};
+use tracing::Level;
+use tracing_subscriber::{layer::SubscriberExt, Registry};
+
+mod helpers;
+mod mocks;
+
+fn severities(buffer: &Arc<Mutex<Vec<u8>>>) -> Vec<String> {
+    let buffer = buffer
+        .lock()
+        .expect("Couldn't get lock on test write target");
+
+    serde_json::Deserializer::from_slice(&buffer)
+        .into_iter::<MockDefaultEvent>()
+        .map(|event| {
+            event
+                .expect("Error converting test buffer to JSON")
+                .severity
+        })
+        .collect()
+}
+
+#[test]
+fn splits_events_between_writers() {
+    let output = Arc::new(Mutex::new(vec![]));
+    let error_output = Arc::new(Mutex::new(vec![]));
+    let (shared_output, shared_error_output) = (output.clone(), error_output.clone());
+
+    let stackdriver = tracing_stackdriver::layer().with_split_writers(
+        move || MockWriter(shared_output.clone()),
+        move || MockWriter(shared_error_output.clone()),
+    );
+
+    tracing::subscriber::with_default(Registry::default().with(stackdriver), || {
+        tracing::info!("info");
+        tracing::warn!("warning");
+        tracing::error!("error");
+    });
+
+    assert_eq!(severities(&output), ["INFO"]);
+    assert_eq!(severities(&error_output), ["WARNING", "ERROR"]);
+}
+
+#[test]
+fn splits_events_between_writers_at_custom_thresholds() {
+    let output = Arc::new(Mutex::new(vec![]));
+    let error_output = Arc::new(Mutex::new(vec![]));
+    let (shared_output, shared_error_output) = (output.clone(), error_output.clone());
+
+    let stackdriver = tracing_stackdriver::layer().with_split_writers_at(
+        Level::ERROR,
+        move || MockWriter(shared_output.clone()),
+        move || MockWriter(shared_error_output.clone()),
+    );
+
+    tracing::subscriber::with_default(Registry::default().with(stackdriver), || {
+        tracing::info!("info");
+        tracing::warn!("warning");
+        tracing::error!("error");
+    });
+
+    assert_eq!(severities(&output), ["INFO", "WARNING"]);
+    assert_eq!(severities(&error_output), ["ERROR"]);
+}